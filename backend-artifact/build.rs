@@ -1,5 +1,180 @@
+use std::{
+    env,
+    ffi::OsStr,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use brotli::CompressorWriter;
+use chrono::{DateTime, SecondsFormat, Utc};
+use flate2::{Compression, write::GzEncoder};
+use sha2::{Digest, Sha256};
 use static_files::resource_dir;
 
-fn main() -> std::io::Result<()> {
-    resource_dir("../yew-app/dist").build()
+use backend_impl::{create_anonymous_schema, create_schema};
+
+const DIST_DIR: &str = "../yew-app/dist";
+
+/// smaller files are not worth the `Content-Encoding`
+const PRECOMPRESS_MIN_SIZE: usize = 1024;
+/// formats which are compressed already
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "avif", "ico", "woff", "woff2", "gz", "br", "zip",
+];
+
+fn main() -> io::Result<()> {
+    write_build_info()?;
+    if !embed_frontend() {
+        return Ok(());
+    }
+    resource_dir(DIST_DIR).build()?;
+    let precompressed = precompress()?;
+    let mut variants = resource_dir(&precompressed);
+    variants
+        .with_generated_filename(out_dir().join("precompressed.rs"))
+        .with_generated_fn("generate_precompressed");
+    variants.build()
+}
+
+/// Provides the build information as compile time environment variables
+fn write_build_info() -> io::Result<()> {
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+    if embed_frontend() {
+        // static-files declares nothing, a new trunk build has to update the embedded files
+        println!("cargo:rerun-if-changed={DIST_DIR}");
+    }
+    println!("cargo:rustc-env=BUILD_GIT_SHA={}", git_sha());
+    println!("cargo:rustc-env=BUILD_TIME={}", build_time());
+    println!("cargo:rustc-env=BUILD_SCHEMA_HASH={}", schema_hash());
+    println!("cargo:rustc-env=BUILD_ASSETS_HASH={}", assets_hash()?);
+    Ok(())
+}
+
+fn git_sha() -> String {
+    if let Ok(sha) = env::var("GIT_SHA") {
+        return sha;
+    }
+    Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|sha| sha.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// honors SOURCE_DATE_EPOCH for reproducible builds
+fn build_time() -> String {
+    env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .and_then(|epoch| DateTime::<Utc>::from_timestamp(epoch, 0))
+        .unwrap_or_else(Utc::now)
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn schema_hash() -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(create_schema().sdl());
+    hasher.update(create_anonymous_schema().sdl());
+    format!("{:x}", hasher.finalize())
+}
+
+/// `embed-frontend` feature, builds without it need no `dist` directory
+fn embed_frontend() -> bool {
+    env::var_os("CARGO_FEATURE_EMBED_FRONTEND").is_some()
+}
+
+fn assets_hash() -> io::Result<String> {
+    if !embed_frontend() {
+        return Ok("none".to_string());
+    }
+    let root = Path::new(DIST_DIR);
+    let mut files = Vec::new();
+    collect_files(root, &mut files)?;
+    files.sort();
+    let mut hasher = Sha256::new();
+    for file in files {
+        let relative = file.strip_prefix(root).unwrap_or(&file);
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update(fs::read(&file)?);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Writes brotli and gzip variants of the dist files as `<file>.br` and `<file>.gz`
+///
+/// Variants which are not smaller than the original are left out.
+fn precompress() -> io::Result<PathBuf> {
+    let target = out_dir().join("precompressed");
+    if target.exists() {
+        fs::remove_dir_all(&target)?;
+    }
+    fs::create_dir_all(&target)?;
+    // the best brotli level takes a while for the wasm module
+    let brotli_quality = if env::var("PROFILE").as_deref() == Ok("release") {
+        11
+    } else {
+        5
+    };
+    let root = Path::new(DIST_DIR);
+    let mut files = Vec::new();
+    collect_files(root, &mut files)?;
+    for file in files {
+        let compressed_format = file
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|extension| {
+                COMPRESSED_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+            });
+        let data = fs::read(&file)?;
+        if compressed_format || data.len() < PRECOMPRESS_MIN_SIZE {
+            continue;
+        }
+        let base = target.join(file.strip_prefix(root).unwrap_or(&file));
+        if let Some(parent) = base.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut brotli = CompressorWriter::new(Vec::new(), 4096, brotli_quality, 22);
+        brotli.write_all(&data)?;
+        write_if_smaller(&base, "br", &brotli.into_inner(), data.len())?;
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::best());
+        gzip.write_all(&data)?;
+        write_if_smaller(&base, "gz", &gzip.finish()?, data.len())?;
+    }
+    Ok(target)
+}
+
+fn write_if_smaller(base: &Path, suffix: &str, data: &[u8], original: usize) -> io::Result<()> {
+    if data.len() >= original {
+        return Ok(());
+    }
+    let mut name = base.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    fs::write(name, data)
+}
+
+fn out_dir() -> PathBuf {
+    PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo"))
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
 }
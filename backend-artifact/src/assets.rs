@@ -0,0 +1,734 @@
+#[cfg(feature = "embed-frontend")]
+use std::collections::HashMap;
+use std::{
+    convert::Infallible,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, mpsc},
+    thread,
+    time::Duration,
+};
+
+#[cfg(feature = "embed-frontend")]
+use actix_web::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING, ETAG, IF_NONE_MATCH, VARY};
+use actix_web::{
+    HttpRequest, HttpResponse,
+    http::{
+        Method,
+        header::{ACCEPT, CACHE_CONTROL, ContentType, HeaderName},
+    },
+    web::{Bytes, Data, block},
+};
+use futures_util::stream;
+use log::{info, warn};
+use notify::{Event, RecursiveMode, Watcher};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+#[cfg(feature = "embed-frontend")]
+use static_files::Resource;
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+use backend_impl::config::{self, FrontendSettings};
+
+#[cfg(feature = "embed-frontend")]
+use crate::build_info::SERVER_INFO;
+use crate::{pwa, runtime_config::RuntimeConfig, security_headers::ScriptHashes};
+
+#[cfg(feature = "embed-frontend")]
+include!(concat!(env!("OUT_DIR"), "/precompressed.rs"));
+
+/// `Content-Encoding` and file suffix of the precompressed variants, preferred first
+#[cfg(feature = "embed-frontend")]
+const ENCODINGS: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+/// paths of the backend which never serve the app
+const API_PREFIXES: [&str; 3] = ["/graphql", "/graphql_anonymous", "/client_log"];
+
+/// file names produced by trunk contain a content hash, they never change
+#[cfg(feature = "embed-frontend")]
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+/// everything else is revalidated with the etag
+#[cfg(feature = "embed-frontend")]
+const REVALIDATE: &str = "no-cache";
+
+/// generated by [pwa]
+const MANIFEST: &str = "/manifest.webmanifest";
+const SERVICE_WORKER: &str = "/sw.js";
+
+/// path prefix a reverse proxy removed from the request
+const X_FORWARDED_PREFIX: HeaderName = HeaderName::from_static("x-forwarded-prefix");
+
+/// event stream announcing rebuilds of the frontend directory
+const LIVE_RELOAD_EVENTS: &str = "/_live_reload";
+const LIVE_RELOAD_SCRIPT: &str = "/_live_reload.js";
+/// relative to the `<base href>` of the page
+const LIVE_RELOAD_JS: &str =
+    "new EventSource(\"_live_reload\").onmessage = () => window.location.reload();\n";
+/// trunk writes a bunch of files per build, the pages reload once it is done
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Error, Debug)]
+pub enum AssetsError {
+    #[cfg(not(feature = "embed-frontend"))]
+    #[error("Built without the embed-frontend feature, set frontend.dir")]
+    NotEmbedded,
+    #[error("Cannot watch frontend directory {0}")]
+    Watch(#[from] notify::Error),
+}
+
+/// Frontend files, `index.html` carries the [RuntimeConfig]
+pub struct Assets {
+    source: Source,
+    runtime_config: RuntimeConfig,
+}
+
+/// embedded at build time or read from `frontend.dir`
+enum Source {
+    #[cfg(feature = "embed-frontend")]
+    Embedded(Embedded),
+    Directory(Directory),
+}
+
+/// Frontend bundle embedded at build time, with brotli and gzip variants
+#[cfg(feature = "embed-frontend")]
+pub struct Embedded {
+    files: HashMap<&'static str, Resource>,
+    precompressed: HashMap<&'static str, Resource>,
+    /// strong etags of files and variants by name
+    etags: HashMap<&'static str, String>,
+    script_hashes: Arc<ScriptHashes>,
+}
+
+/// Frontend read from disk on every request, for development
+pub struct Directory {
+    root: PathBuf,
+    live_reload: Option<broadcast::Sender<()>>,
+}
+
+impl Assets {
+    pub fn new(
+        settings: &FrontendSettings,
+        runtime_config: RuntimeConfig,
+    ) -> Result<Self, AssetsError> {
+        let source = match settings.dir() {
+            Some(dir) => {
+                info!("Serving frontend from {}", dir.display());
+                Source::Directory(Directory::new(dir.to_path_buf(), settings.live_reload())?)
+            }
+            #[cfg(feature = "embed-frontend")]
+            None => Source::Embedded(Embedded::new()),
+            #[cfg(not(feature = "embed-frontend"))]
+            None => return Err(AssetsError::NotEmbedded),
+        };
+        Ok(Self {
+            source,
+            runtime_config,
+        })
+    }
+
+    /// `sw.js` caching the hashed files of the current build
+    async fn service_worker(&self) -> HttpResponse {
+        let (version, shell) = match &self.source {
+            #[cfg(feature = "embed-frontend")]
+            Source::Embedded(embedded) => {
+                let mut shell: Vec<String> = embedded
+                    .files
+                    .keys()
+                    .filter(|name| is_hashed(name))
+                    .map(|name| name.to_string())
+                    .collect();
+                shell.sort();
+                (SERVER_INFO.assets_hash.to_string(), shell)
+            }
+            Source::Directory(directory) => {
+                let shell = directory.shell().await;
+                // the names change with the content
+                let version = hex(&Sha256::digest(shell.join("\n"))[..8]);
+                (version, shell)
+            }
+        };
+        pwa::service_worker(&version, &shell)
+    }
+}
+
+#[cfg(feature = "embed-frontend")]
+impl Embedded {
+    fn new() -> Self {
+        let files = crate::generate();
+        let precompressed = generate_precompressed();
+        let etags = files
+            .iter()
+            .chain(precompressed.iter())
+            .map(|(name, resource)| {
+                let digest = Sha256::digest(resource.data);
+                (*name, format!("\"{}\"", hex(&digest[..16])))
+            })
+            .collect();
+        let script_hashes = Arc::new(ScriptHashes::of_html(
+            files.get("index.html").map_or(&[][..], |index| index.data),
+        ));
+        Self {
+            files,
+            precompressed,
+            etags,
+            script_hashes,
+        }
+    }
+
+    async fn serve(&self, request: &HttpRequest, runtime_config: &RuntimeConfig) -> HttpResponse {
+        let path = file_path(relative_path(request));
+        let found = self.files.get_key_value(path).or_else(|| {
+            is_navigation(request)
+                .then(|| self.files.get_key_value("index.html"))
+                .flatten()
+        });
+        let Some((&path, resource)) = found else {
+            return not_found(request);
+        };
+        if path == "index.html" {
+            // rendered per request, so neither precompressed nor cached without revalidation
+            let html = render_index(request, resource.data, runtime_config).await;
+            let etag = format!("\"{}\"", hex(&Sha256::digest(&html)[..16]));
+            let mut response = respond(
+                request,
+                Bytes::from(html),
+                resource.mime_type,
+                None,
+                etag,
+                REVALIDATE,
+            );
+            response.extensions_mut().insert(self.script_hashes.clone());
+            return response;
+        }
+        let accept_encoding = request
+            .headers()
+            .get(ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let variant = ENCODINGS
+            .iter()
+            .filter(|(encoding, _)| accepts(accept_encoding, encoding))
+            .find_map(|(encoding, suffix)| {
+                let (name, variant) = self
+                    .precompressed
+                    .get_key_value(format!("{path}.{suffix}").as_str())?;
+                Some((*encoding, *name, variant.data))
+            });
+        let (encoding, name, data) = match variant {
+            Some((encoding, name, data)) => (Some(encoding), name, data),
+            None => (None, path, resource.data),
+        };
+        let etag = self.etags.get(name).cloned().unwrap_or_default();
+        let cache_control = if is_hashed(path) {
+            IMMUTABLE
+        } else {
+            REVALIDATE
+        };
+        respond(
+            request,
+            Bytes::from_static(data),
+            resource.mime_type,
+            encoding,
+            etag,
+            cache_control,
+        )
+    }
+}
+
+#[cfg(feature = "embed-frontend")]
+fn respond(
+    request: &HttpRequest,
+    data: Bytes,
+    mime_type: &str,
+    encoding: Option<&str>,
+    etag: String,
+    cache_control: &'static str,
+) -> HttpResponse {
+    let not_modified = request
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|if_none_match| matches_etag(if_none_match, &etag));
+    let mut response = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    response
+        .insert_header((ETAG, etag))
+        .insert_header((CACHE_CONTROL, cache_control))
+        .insert_header((VARY, "Accept-Encoding"));
+    if not_modified {
+        return response.finish();
+    }
+    response.content_type(mime_type);
+    if let Some(encoding) = encoding {
+        response.insert_header((CONTENT_ENCODING, encoding));
+    }
+    response.body(data)
+}
+
+impl Directory {
+    fn new(root: PathBuf, live_reload: bool) -> Result<Self, AssetsError> {
+        let live_reload = if live_reload {
+            Some(watch(&root)?)
+        } else {
+            None
+        };
+        Ok(Self { root, live_reload })
+    }
+
+    async fn serve(&self, request: &HttpRequest, runtime_config: &RuntimeConfig) -> HttpResponse {
+        if let Some(live_reload) = &self.live_reload {
+            match relative_path(request) {
+                LIVE_RELOAD_EVENTS => return reload_events(live_reload.subscribe()),
+                LIVE_RELOAD_SCRIPT => {
+                    return HttpResponse::Ok()
+                        .content_type("text/javascript")
+                        .insert_header((CACHE_CONTROL, "no-store"))
+                        .body(LIVE_RELOAD_JS);
+                }
+                _ => {}
+            }
+        }
+        let path = file_path(relative_path(request));
+        let (path, data) = match self.read(path).await {
+            Some(data) => (path, data),
+            None if is_navigation(request) => match self.read("index.html").await {
+                Some(data) => ("index.html", data),
+                None => return not_found(request),
+            },
+            None => return not_found(request),
+        };
+        let (data, script_hashes) = if path == "index.html" {
+            let script_hashes = Arc::new(ScriptHashes::of_html(&data));
+            let html = render_index(request, &data, runtime_config).await;
+            (self.with_live_reload(html), Some(script_hashes))
+        } else {
+            (data, None)
+        };
+        let mut response = HttpResponse::Ok()
+            .content_type(mime_guess::from_path(path).first_or_octet_stream().as_ref())
+            // the files change with every build
+            .insert_header((CACHE_CONTROL, "no-store"))
+            .body(data);
+        if let Some(script_hashes) = script_hashes {
+            response.extensions_mut().insert(script_hashes);
+        }
+        response
+    }
+
+    fn with_live_reload(&self, html: Vec<u8>) -> Vec<u8> {
+        if self.live_reload.is_none() {
+            return html;
+        }
+        // loaded from the own origin, so the Content-Security-Policy needs no hash for it
+        let script = format!(
+            "<script src=\"{}\"></script>",
+            LIVE_RELOAD_SCRIPT.trim_start_matches('/')
+        );
+        insert_before(&html, "</body>", &script)
+    }
+
+    /// hashed files at the top of the directory, sorted
+    async fn shell(&self) -> Vec<String> {
+        let root = self.root.clone();
+        let names = block(move || -> std::io::Result<Vec<String>> {
+            let mut names = Vec::new();
+            for entry in std::fs::read_dir(root)? {
+                let name = entry?.file_name().to_string_lossy().into_owned();
+                if is_hashed(&name) {
+                    names.push(name);
+                }
+            }
+            Ok(names)
+        })
+        .await;
+        let mut names = match names {
+            Ok(Ok(names)) => names,
+            _ => Vec::new(),
+        };
+        names.sort();
+        names
+    }
+
+    /// `None` for missing files and paths leaving the directory
+    async fn read(&self, path: &str) -> Option<Vec<u8>> {
+        let relative = Path::new(path);
+        if path.is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+        let file = self.root.join(relative);
+        block(move || std::fs::read(file)).await.ok()?.ok()
+    }
+}
+
+/// Sends an event to the subscribers whenever files below `root` change
+fn watch(root: &Path) -> notify::Result<broadcast::Sender<()>> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let _ = sender.send(event);
+    })?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    let (reload, _) = broadcast::channel(1);
+    let subscribers = reload.clone();
+    thread::spawn(move || {
+        // moved into the thread to keep it running
+        let _watcher = watcher;
+        while let Ok(event) = receiver.recv() {
+            if let Err(error) = event {
+                warn!("Error watching frontend: {error}");
+                continue;
+            }
+            while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+            info!("Frontend changed, reloading pages");
+            let _ = subscribers.send(());
+        }
+    });
+    Ok(reload)
+}
+
+/// `text/event-stream` with a message per change
+fn reload_events(receiver: broadcast::Receiver<()>) -> HttpResponse {
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        // a lagging receiver missed changes, which is a reason to reload as well
+        match receiver.recv().await {
+            Err(broadcast::error::RecvError::Closed) => None,
+            _ => Some((
+                Ok::<_, Infallible>(Bytes::from_static(b"data: reload\n\n")),
+                receiver,
+            )),
+        }
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((CACHE_CONTROL, "no-store"))
+        .streaming(events)
+}
+
+/// `index.html` with the `<base href>` the browser sees and the [RuntimeConfig]
+async fn render_index(
+    request: &HttpRequest,
+    html: &[u8],
+    runtime_config: &RuntimeConfig,
+) -> Vec<u8> {
+    // trunk builds with relative urls, they resolve against the base
+    let base = format!("<base href=\"{}/\">", public_base_path(request));
+    let mut html = insert_after(html, "<head>", &base);
+    if let Some(script) = runtime_config.script_tag().await {
+        html = insert_before(&html, "</head>", &script);
+    }
+    html
+}
+
+/// `server.base_path`, behind a trusted proxy prefixed with its `X-Forwarded-Prefix`
+fn public_base_path(request: &HttpRequest) -> String {
+    let settings = config::current();
+    let server = settings.server();
+    let forwarded = forwarded_prefix(request, server.trust_forwarded_prefix());
+    prefixed_base_path(forwarded, &server.base_path())
+}
+
+/// `X-Forwarded-Prefix` if the proxy is trusted, `Forwarded` has no parameter for a prefix
+fn forwarded_prefix(request: &HttpRequest, trusted: bool) -> Option<&str> {
+    trusted
+        .then(|| request.headers().get(X_FORWARDED_PREFIX))
+        .flatten()
+        .and_then(|value| value.to_str().ok())
+}
+
+/// `base_path` below `forwarded_prefix`, unless the prefix is not a plain path
+fn prefixed_base_path(forwarded_prefix: Option<&str>, base_path: &str) -> String {
+    let prefix = forwarded_prefix
+        .map(|prefix| prefix.trim_end_matches('/'))
+        // ends up in an attribute of index.html
+        .filter(|prefix| {
+            prefix.starts_with('/')
+                && prefix
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "/-._~%".contains(c))
+        })
+        .unwrap_or_default();
+    format!("{prefix}{base_path}")
+}
+
+/// path below `server.base_path`
+fn relative_path(request: &HttpRequest) -> &str {
+    request.match_info().unprocessed()
+}
+
+/// file below the frontend root, the root itself is `index.html` whatever the request accepts
+fn file_path(relative_path: &str) -> &str {
+    match relative_path.trim_start_matches('/') {
+        "" => "index.html",
+        path => path,
+    }
+}
+
+/// `snippet` behind the first `start_tag`, prepended if there is none
+fn insert_after(html: &[u8], start_tag: &str, snippet: &str) -> Vec<u8> {
+    let html = String::from_utf8_lossy(html);
+    match html.find(start_tag) {
+        Some(start) => {
+            let end = start + start_tag.len();
+            format!("{}{snippet}{}", &html[..end], &html[end..])
+        }
+        None => format!("{snippet}{html}"),
+    }
+    .into_bytes()
+}
+
+/// `snippet` in front of the last `end_tag`, appended if there is none
+fn insert_before(html: &[u8], end_tag: &str, snippet: &str) -> Vec<u8> {
+    let html = String::from_utf8_lossy(html);
+    match html.rfind(end_tag) {
+        Some(end) => format!("{}{snippet}{}", &html[..end], &html[end..]),
+        None => format!("{html}{snippet}"),
+    }
+    .into_bytes()
+}
+
+/// Serves the frontend, navigation requests to unknown paths get `index.html` for the router
+/// of the app
+pub async fn serve(request: HttpRequest, assets: Data<Assets>) -> HttpResponse {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return HttpResponse::MethodNotAllowed().finish();
+    }
+    match relative_path(&request) {
+        MANIFEST => return pwa::manifest(),
+        SERVICE_WORKER => return assets.service_worker().await,
+        _ => {}
+    }
+    let runtime_config = &assets.runtime_config;
+    match &assets.source {
+        #[cfg(feature = "embed-frontend")]
+        Source::Embedded(embedded) => embedded.serve(&request, runtime_config).await,
+        Source::Directory(directory) => directory.serve(&request, runtime_config).await,
+    }
+}
+
+/// Requests to api resources their routes don't take, like a `GET /graphql`
+pub async fn api_fallback(request: HttpRequest) -> HttpResponse {
+    not_found(&request)
+}
+
+/// A page of the app opened in the browser, as opposed to a missing asset or api call
+fn is_navigation(request: &HttpRequest) -> bool {
+    let path = relative_path(request);
+    let accept = request
+        .headers()
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let has_extension = path
+        .rsplit('/')
+        .next()
+        .is_some_and(|name| name.contains('.'));
+    accepts(accept, "text/html") && !has_extension && !is_api(path)
+}
+
+fn is_api(path: &str) -> bool {
+    API_PREFIXES.iter().any(|prefix| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+fn not_found(request: &HttpRequest) -> HttpResponse {
+    if is_api(relative_path(request)) {
+        HttpResponse::NotFound().json(json!({"errors": [{"message": "Not found"}]}))
+    } else {
+        HttpResponse::NotFound()
+            .content_type(ContentType::plaintext())
+            .body(format!("{} not found", request.path()))
+    }
+}
+
+/// `yew-app-1a2b3c4d5e6f7a8b.js` or `yew-app-1a2b3c4d5e6f7a8b_bg.wasm`
+fn is_hashed(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let stem = name.split('.').next().unwrap_or(name);
+    let stem = stem.strip_suffix("_bg").unwrap_or(stem);
+    stem.rsplit_once('-')
+        .is_some_and(|(_, hash)| hash.len() >= 16 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// weak comparison as required for `If-None-Match`
+#[cfg(feature = "embed-frontend")]
+fn matches_etag(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// `value` is listed in an `Accept` or `Accept-Encoding` header without `q=0`
+fn accepts(header: &str, value: &str) -> bool {
+    header.split(',').any(|entry| {
+        let mut parts = entry.split(';').map(str::trim);
+        let name_matches = parts.next().is_some_and(|name| matches_range(name, value));
+        name_matches
+            && parts.all(|parameter| {
+                parameter
+                    .strip_prefix("q=")
+                    .is_none_or(|quality| quality.parse::<f32>().is_ok_and(|q| q > 0.0))
+            })
+    })
+}
+
+/// `*`, `*/*` and `type/*` are wildcards
+fn matches_range(range: &str, value: &str) -> bool {
+    if range == "*" || range == "*/*" || range.eq_ignore_ascii_case(value) {
+        return true;
+    }
+    match (range.strip_suffix("/*"), value.split_once('/')) {
+        (Some(range_type), Some((value_type, _))) => range_type.eq_ignore_ascii_case(value_type),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::{StatusCode, header::CONTENT_TYPE},
+        test::TestRequest,
+    };
+
+    use super::*;
+
+    #[test]
+    fn api_paths_get_json_not_found() {
+        let request = TestRequest::get().uri("/graphql").to_http_request();
+        let response = not_found(&request);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+    }
+
+    #[test]
+    fn accepts_listed_encodings() {
+        assert!(accepts("gzip, deflate, br", "br"));
+        assert!(accepts("br;q=1.0, gzip;q=0.5", "gzip"));
+        assert!(accepts("*", "br"));
+        assert!(!accepts("br;q=0, gzip", "br"));
+        assert!(!accepts("identity", "gzip"));
+        assert!(!accepts("", "gzip"));
+    }
+
+    #[test]
+    fn accepts_media_ranges() {
+        assert!(accepts("text/html,*/*;q=0.8", "text/html"));
+        assert!(accepts("TEXT/HTML", "text/html"));
+        assert!(accepts("*/*", "text/html"));
+        assert!(accepts("text/*;q=0.5", "text/html"));
+        assert!(!accepts("application/*", "text/html"));
+        assert!(!accepts("*/*;q=0", "text/html"));
+        assert!(!accepts("application/json", "text/html"));
+    }
+
+    #[cfg(feature = "embed-frontend")]
+    #[test]
+    fn etags_match_weakly() {
+        let etag = "\"abc\"";
+        assert!(matches_etag("\"abc\"", etag));
+        assert!(matches_etag("W/\"abc\"", etag));
+        assert!(matches_etag("\"xyz\", W/\"abc\"", etag));
+        assert!(matches_etag("*", etag));
+        assert!(!matches_etag("\"abcd\"", etag));
+        assert!(!matches_etag("abc", etag));
+    }
+
+    #[test]
+    fn trunk_names_are_hashed() {
+        assert!(is_hashed("yew-app-1a2b3c4d5e6f7a8b.js"));
+        assert!(is_hashed("yew-app-1a2b3c4d5e6f7a8b_bg.wasm"));
+        assert!(is_hashed("snippets/style-0123456789abcdef.css"));
+        assert!(!is_hashed("index.html"));
+        assert!(!is_hashed("icon.svg"));
+        assert!(!is_hashed("yew-app-1a2b3c.js"));
+        assert!(!is_hashed("yew-app-1a2b3c4d5e6f7a8z.js"));
+    }
+
+    fn get(path: &str, accept: &str) -> HttpRequest {
+        TestRequest::get()
+            .uri(path)
+            .insert_header((ACCEPT, accept))
+            .to_http_request()
+    }
+
+    #[test]
+    fn api_prefixes_match_whole_segments() {
+        assert!(is_api("/graphql"));
+        assert!(is_api("/graphql/"));
+        assert!(is_api("/graphql_anonymous"));
+        assert!(is_api("/client_log"));
+        assert!(!is_api("/graphqlfoo"));
+        assert!(!is_api("/add"));
+        assert!(!is_api(""));
+    }
+
+    #[test]
+    fn navigation_wants_html_without_extension() {
+        assert!(is_navigation(&get("/add", "text/html,*/*;q=0.8")));
+        assert!(is_navigation(&get("/add", "*/*")));
+        assert!(is_navigation(&get("/graphqlfoo", "text/html")));
+        assert!(!is_navigation(&get("/add", "application/json")));
+        assert!(!is_navigation(&get("/missing.js", "*/*")));
+        assert!(!is_navigation(&get("/graphql", "text/html")));
+        assert!(!is_navigation(&get("/graphql/x", "*/*")));
+    }
+
+    #[test]
+    fn root_serves_index() {
+        assert_eq!(relative_path(&get("/add?x=1", "*/*")), "/add");
+        assert_eq!(file_path(""), "index.html");
+        assert_eq!(file_path("/"), "index.html");
+        assert_eq!(file_path("/icon.svg"), "icon.svg");
+        assert_eq!(file_path("/assets/app.css"), "assets/app.css");
+    }
+
+    #[test]
+    fn snippets_go_into_head() {
+        let html = b"<html><head><title>App</title></head><body></body></html>";
+        let html = insert_after(html, "<head>", "<base href=\"/app/\">");
+        let html = insert_before(&html, "</head>", "<script></script>");
+        assert_eq!(
+            String::from_utf8(html).unwrap(),
+            "<html><head><base href=\"/app/\"><title>App</title><script></script></head>\
+             <body></body></html>"
+        );
+        assert_eq!(insert_after(b"<p>", "<head>", "<base>"), b"<base><p>");
+        assert_eq!(insert_before(b"<p>", "</head>", "<script>"), b"<p><script>");
+    }
+
+    #[test]
+    fn forwarded_prefix_needs_trust() {
+        let request = TestRequest::get()
+            .insert_header((X_FORWARDED_PREFIX, "/proxy"))
+            .to_http_request();
+        assert_eq!(forwarded_prefix(&request, false), None);
+        assert_eq!(forwarded_prefix(&request, true), Some("/proxy"));
+        let request = TestRequest::get()
+            .insert_header(("forwarded", "host=corp;proto=https"))
+            .to_http_request();
+        assert_eq!(forwarded_prefix(&request, true), None);
+    }
+
+    #[test]
+    fn forwarded_prefix_must_be_plain_path() {
+        assert_eq!(prefixed_base_path(None, "/app"), "/app");
+        assert_eq!(prefixed_base_path(Some("/proxy/"), "/app"), "/proxy/app");
+        assert_eq!(prefixed_base_path(Some("/proxy"), ""), "/proxy");
+        assert_eq!(prefixed_base_path(Some("proxy"), "/app"), "/app");
+        assert_eq!(prefixed_base_path(Some("/\"><script>"), ""), "");
+    }
+}
@@ -0,0 +1,42 @@
+use actix_web::{get, web::Json};
+use prometheus::{IntGaugeVec, Opts, Registry};
+
+use backend_impl::ServerInfo;
+
+/// Build of this binary, collected by build.rs
+pub const SERVER_INFO: ServerInfo = ServerInfo {
+    version: env!("CARGO_PKG_VERSION"),
+    git_sha: env!("BUILD_GIT_SHA"),
+    build_time: env!("BUILD_TIME"),
+    schema_hash: env!("BUILD_SCHEMA_HASH"),
+    assets_hash: env!("BUILD_ASSETS_HASH"),
+};
+
+/// Exposes the build information as constant gauge `build_info`
+pub fn register_build_info(registry: &Registry) -> Result<(), prometheus::Error> {
+    let gauge = IntGaugeVec::new(
+        Opts::new("build_info", "Build of the running server"),
+        &[
+            "version",
+            "git_sha",
+            "build_time",
+            "schema_hash",
+            "assets_hash",
+        ],
+    )?;
+    gauge
+        .with_label_values(&[
+            SERVER_INFO.version,
+            SERVER_INFO.git_sha,
+            SERVER_INFO.build_time,
+            SERVER_INFO.schema_hash,
+            SERVER_INFO.assets_hash,
+        ])
+        .set(1);
+    registry.register(Box::new(gauge))
+}
+
+#[get("/info")]
+pub async fn info() -> Json<ServerInfo> {
+    Json(SERVER_INFO)
+}
@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use backend_impl::config::ConfigLocation;
+
+/// Backend of the rust fullstack application
+#[derive(Parser, Debug)]
+#[command(version)]
+pub struct Cli {
+    /// Configuration file
+    #[arg(long, env = "APP_CONFIG", default_value = "config.yaml", global = true)]
+    config: PathBuf,
+    /// Profile applied on top of the configuration file, e.g. `prod` loads `config.prod.yaml`
+    #[arg(long, env = "APP_PROFILE", global = true)]
+    profile: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, Copy)]
+pub enum Command {
+    /// Run the api and management servers (default)
+    Serve,
+    /// Print the SDL of a graphql schema
+    PrintSchema {
+        #[arg(value_enum, default_value_t = SchemaKind::Api)]
+        schema: SchemaKind,
+    },
+    /// Validate the configuration and print the effective settings
+    CheckConfig,
+    /// Probe the health endpoint on the management port, fails if it does not answer with 200
+    Healthcheck {
+        /// Seconds to wait for the answer, at least 1
+        #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..))]
+        timeout: u64,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum SchemaKind {
+    /// Schema served on `/graphql`
+    Api,
+    /// Schema served on `/graphql_anonymous`
+    Anonymous,
+}
+
+impl Cli {
+    pub fn command(&self) -> Command {
+        self.command.unwrap_or(Command::Serve)
+    }
+
+    pub fn config_location(&self) -> ConfigLocation {
+        ConfigLocation {
+            path: self.config.clone(),
+            profile: self.profile.clone(),
+        }
+    }
+}
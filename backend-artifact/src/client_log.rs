@@ -0,0 +1,250 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    HttpRequest, HttpResponse,
+    web::{Data, Json},
+};
+use log::{Level, log};
+use prometheus::{IntCounter, IntCounterVec, Opts, Registry};
+use serde::Deserialize;
+
+use backend_impl::config;
+
+/// Log target used for all entries reported by the frontend
+const CLIENT_LOG_TARGET: &str = "client";
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+const MAX_MESSAGE_LENGTH: usize = 4096;
+/// bytes kept of the other reported fields
+const MAX_FIELD_LENGTH: usize = 256;
+/// the oldest window is dropped for a new client beyond this
+const MAX_TRACKED_CLIENTS: usize = 1024;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientLogLevel {
+    Warn,
+    Error,
+    Panic,
+}
+
+impl ClientLogLevel {
+    fn label(self) -> &'static str {
+        match self {
+            ClientLogLevel::Warn => "warn",
+            ClientLogLevel::Error => "error",
+            ClientLogLevel::Panic => "panic",
+        }
+    }
+    fn log_level(self) -> Level {
+        match self {
+            ClientLogLevel::Warn => Level::Warn,
+            ClientLogLevel::Error | ClientLogLevel::Panic => Level::Error,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ClientLogEntry {
+    level: ClientLogLevel,
+    message: String,
+    #[serde(default)]
+    target: Option<String>,
+    #[serde(default)]
+    route: Option<String>,
+    #[serde(default)]
+    user: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+struct RateWindow {
+    started: Instant,
+    count: u32,
+}
+
+/// Receives batches of warnings, errors and panics from the frontend
+pub struct ClientLogSink {
+    entries_counter: IntCounterVec,
+    dropped_counter: IntCounter,
+    windows: Mutex<HashMap<String, RateWindow>>,
+}
+
+impl ClientLogSink {
+    pub fn new(registry: &Registry) -> Result<Self, prometheus::Error> {
+        let entries_counter = IntCounterVec::new(
+            Opts::new("client_log_entries", "Log entries reported by the frontend"),
+            &["level"],
+        )?;
+        let dropped_counter = IntCounter::new(
+            "client_log_dropped",
+            "Frontend log entries dropped by rate limiting",
+        )?;
+        registry.register(Box::new(entries_counter.clone()))?;
+        registry.register(Box::new(dropped_counter.clone()))?;
+        Ok(Self {
+            entries_counter,
+            dropped_counter,
+            windows: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// returns how many of the requested entries the client may still send in the current window
+    fn admit(&self, client: &str, requested: usize, limit: u32, now: Instant) -> usize {
+        let mut windows = self
+            .windows
+            .lock()
+            .expect("client log rate limiter poisoned");
+        if !windows.contains_key(client) && windows.len() >= MAX_TRACKED_CLIENTS {
+            windows.retain(|_, window| now.duration_since(window.started) < RATE_WINDOW);
+            let oldest = (windows.len() >= MAX_TRACKED_CLIENTS)
+                .then(|| windows.iter().min_by_key(|(_, window)| window.started))
+                .flatten()
+                .map(|(client, _)| client.clone());
+            if let Some(oldest) = oldest {
+                windows.remove(&oldest);
+            }
+        }
+        let window = windows.entry(client.to_string()).or_insert(RateWindow {
+            started: now,
+            count: 0,
+        });
+        if now.duration_since(window.started) >= RATE_WINDOW {
+            window.started = now;
+            window.count = 0;
+        }
+        let admitted = limit.saturating_sub(window.count).min(requested as u32);
+        window.count += admitted;
+        admitted as usize
+    }
+}
+
+pub async fn client_log(
+    sink: Data<ClientLogSink>,
+    request: HttpRequest,
+    entries: Json<Vec<ClientLogEntry>>,
+) -> HttpResponse {
+    let settings = config::current();
+    // the forwarded headers are set by the client unless a proxy replaces them
+    let client = if settings.server().trust_forwarded_for() {
+        request
+            .connection_info()
+            .realip_remote_addr()
+            .map(str::to_string)
+    } else {
+        request.peer_addr().map(|address| address.ip().to_string())
+    }
+    .unwrap_or_default();
+    let entries = entries.into_inner();
+    let limit = settings.client_log().rate_limit();
+    let admitted = sink.admit(&client, entries.len(), limit, Instant::now());
+    let dropped = entries.len() - admitted;
+    let field = |value: &Option<String>| {
+        value.as_deref().map_or_else(
+            || "-".to_string(),
+            |value| sanitize(value, MAX_FIELD_LENGTH),
+        )
+    };
+    for entry in entries.into_iter().take(admitted) {
+        sink.entries_counter
+            .with_label_values(&[entry.level.label()])
+            .inc();
+        log!(
+            target: CLIENT_LOG_TARGET,
+            entry.level.log_level(),
+            "{} from {client} (user: {}, route: {}, version: {}, source: {}): {}",
+            entry.level.label(),
+            field(&entry.user),
+            field(&entry.route),
+            field(&entry.version),
+            field(&entry.target),
+            sanitize(&entry.message, MAX_MESSAGE_LENGTH),
+        );
+    }
+    if dropped > 0 {
+        sink.dropped_counter.inc_by(dropped as u64);
+        HttpResponse::TooManyRequests().finish()
+    } else {
+        HttpResponse::Accepted().finish()
+    }
+}
+
+/// At most `max_length` bytes of `value` with control characters escaped, so a reported value
+/// cannot forge further log lines
+fn sanitize(value: &str, max_length: usize) -> String {
+    let mut end = value.len().min(max_length);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut sanitized = String::with_capacity(end);
+    for c in value[..end].chars() {
+        if c.is_control() {
+            sanitized.extend(c.escape_default());
+        } else {
+            sanitized.push(c);
+        }
+    }
+    if end < value.len() {
+        sanitized.push('…');
+    }
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sink() -> ClientLogSink {
+        ClientLogSink::new(&Registry::new()).unwrap()
+    }
+
+    #[test]
+    fn admit_splits_quota_across_batches() {
+        let sink = sink();
+        let now = Instant::now();
+        assert_eq!(sink.admit("a", 3, 5, now), 3);
+        assert_eq!(sink.admit("a", 4, 5, now), 2);
+        assert_eq!(sink.admit("a", 1, 5, now), 0);
+        assert_eq!(sink.admit("b", 4, 5, now), 4);
+    }
+
+    #[test]
+    fn admit_resets_after_window() {
+        let sink = sink();
+        let now = Instant::now();
+        assert_eq!(sink.admit("a", 5, 5, now), 5);
+        assert_eq!(sink.admit("a", 1, 5, now + RATE_WINDOW / 2), 0);
+        assert_eq!(sink.admit("a", 5, 5, now + RATE_WINDOW), 5);
+    }
+
+    #[test]
+    fn admit_tracks_limited_number_of_clients() {
+        let sink = sink();
+        let now = Instant::now();
+        for client in 0..MAX_TRACKED_CLIENTS {
+            sink.admit(
+                &client.to_string(),
+                1,
+                5,
+                now + Duration::from_millis(client as u64),
+            );
+        }
+        let later = now + Duration::from_secs(1);
+        assert_eq!(sink.admit("new", 1, 5, later), 1);
+        let windows = sink.windows.lock().unwrap();
+        assert_eq!(windows.len(), MAX_TRACKED_CLIENTS);
+        // the oldest window made room
+        assert!(!windows.contains_key("0"));
+        assert!(windows.contains_key("1"));
+    }
+
+    #[test]
+    fn sanitize_escapes_and_truncates() {
+        assert_eq!(sanitize("a\nb\u{1b}[31m", 100), "a\\nb\\u{1b}[31m");
+        assert_eq!(sanitize("äöü", 3), "ä…");
+        assert_eq!(sanitize("short", 100), "short");
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream},
+    os::unix::net::UnixStream,
+    time::Duration,
+};
+
+use rustls::{ClientConnection, StreamOwned, pki_types::ServerName};
+
+use backend_impl::{
+    config::{ConfigLocation, ListenAddress},
+    create_anonymous_schema, create_schema, logging,
+};
+
+use crate::{
+    BackendError,
+    cli::SchemaKind,
+    tls::{TlsError, healthcheck_client_config},
+};
+
+pub fn print_schema(kind: SchemaKind) {
+    let sdl = match kind {
+        SchemaKind::Api => create_schema().sdl(),
+        SchemaKind::Anonymous => create_anonymous_schema().sdl(),
+    };
+    print!("{sdl}");
+}
+
+/// Prints the settings as the server would see them, after profile and environment overrides
+pub fn check_config(location: &ConfigLocation) -> Result<(), BackendError> {
+    // shows deprecation warnings of the configuration
+    logging::init("warn")?;
+    let settings = location.load()?;
+    println!("{}", serde_json::to_string_pretty(&settings)?);
+    eprintln!("Configuration {:?} is valid", location.files());
+    Ok(())
+}
+
+/// Requests `/health` from the first mgmt listener with HTTP/1.0, over TLS with `tls.mgmt`
+///
+/// The container image has no shell or curl, so this is used as its `HEALTHCHECK`.
+pub fn healthcheck(location: &ConfigLocation, timeout: Duration) -> Result<(), BackendError> {
+    let settings = location.load()?;
+    let Some(address) = settings.server().mgmt_listen().into_iter().next() else {
+        return Err(io::Error::other("No mgmt listener configured").into());
+    };
+    let status = match &address {
+        ListenAddress::Tcp(address) => {
+            let address = match address.ip() {
+                ip if ip.is_unspecified() => {
+                    SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), address.port())
+                }
+                _ => *address,
+            };
+            let stream = TcpStream::connect_timeout(&address, timeout)?;
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout))?;
+            if settings.server().tls().is_some_and(|tls| tls.mgmt()) {
+                let server_name = ServerName::IpAddress(address.ip().into());
+                let connection = ClientConnection::new(healthcheck_client_config()?, server_name)
+                    .map_err(TlsError::from)?;
+                request_health(StreamOwned::new(connection, stream), &address.to_string())?
+            } else {
+                request_health(stream, &address.to_string())?
+            }
+        }
+        ListenAddress::Unix(path) => {
+            let stream = UnixStream::connect(path)?;
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout))?;
+            request_health(stream, "localhost")?
+        }
+    };
+    if status.split_whitespace().nth(1) == Some("200") {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("Unhealthy {address}: {status}")).into())
+    }
+}
+
+/// returns the status line
+fn request_health(mut stream: impl Read + Write, host: &str) -> io::Result<String> {
+    write!(stream, "GET /health HTTP/1.0\r\nHost: {host}\r\n\r\n")?;
+    stream.flush()?;
+    let mut status = String::new();
+    BufReader::new(stream).read_line(&mut status)?;
+    Ok(status.trim_end().to_string())
+}
@@ -0,0 +1,48 @@
+use log::error;
+use prometheus::{IntCounterVec, IntGauge, Opts, Registry};
+
+use backend_impl::{
+    config::{self, ReloadError, Reloaded, Settings},
+    logging::LogControl,
+};
+
+use crate::BackendError;
+
+/// Applies the live parts of the configuration which are not read on every use
+pub fn apply_log_filter(settings: &Settings, log_control: &LogControl) {
+    if let Err(error) = log_control.apply_configured(settings.log().filter()) {
+        error!("Invalid log filter in configuration: {error}");
+    }
+}
+
+/// Reloads the configuration on change and reports the outcome as metrics
+pub fn watch_config(registry: &Registry, log_control: LogControl) -> Result<(), BackendError> {
+    let reloads = IntCounterVec::new(
+        Opts::new("config_reloads", "Attempts to reload the configuration"),
+        &["result"],
+    )?;
+    let last_reload_success = IntGauge::new(
+        "config_last_reload_success",
+        "1 if the last configuration reload succeeded",
+    )?;
+    registry.register(Box::new(reloads.clone()))?;
+    registry.register(Box::new(last_reload_success.clone()))?;
+    last_reload_success.set(1);
+
+    config::watch(move |result| {
+        let label = match result {
+            Ok(Reloaded::Applied { previous, current }) => {
+                if previous.log().filter() != current.log().filter() {
+                    apply_log_filter(current, &log_control);
+                }
+                "applied"
+            }
+            Ok(Reloaded::Unchanged) => "unchanged",
+            Err(ReloadError::RestartRequired(_)) => "rejected",
+            Err(ReloadError::Load(_)) => "failed",
+        };
+        reloads.with_label_values(&[label]).inc();
+        last_reload_success.set(i64::from(result.is_ok()));
+    })?;
+    Ok(())
+}
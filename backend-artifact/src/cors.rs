@@ -0,0 +1,27 @@
+use actix_cors::Cors;
+use actix_web::middleware::Condition;
+
+use backend_impl::config::{CorsOrigin, CorsPolicy};
+
+/// Middleware answering preflight requests and adding the CORS headers of `policy`
+///
+/// Without a policy cross origin requests stay denied by the browser.
+pub fn cors(policy: Option<&CorsPolicy>) -> Condition<Cors> {
+    let Some(policy) = policy else {
+        return Condition::new(false, Cors::default());
+    };
+    let mut cors = Cors::default()
+        .allowed_methods(policy.allowed_methods())
+        .allowed_headers(policy.allowed_headers())
+        .max_age(policy.max_age());
+    for origin in policy.allowed_origins() {
+        cors = match origin {
+            CorsOrigin::Any => cors.allow_any_origin(),
+            CorsOrigin::Origin(origin) => cors.allowed_origin(origin),
+        };
+    }
+    if policy.allow_credentials() {
+        cors = cors.supports_credentials();
+    }
+    Condition::new(true, cors)
+}
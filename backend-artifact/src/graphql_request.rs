@@ -0,0 +1,44 @@
+use actix_web::{
+    HttpRequest, HttpResponse,
+    http::{StatusCode, header::CONTENT_TYPE},
+    web::Bytes,
+};
+use async_graphql::{
+    ErrorExtensionValues, Request, Response, ServerError,
+    http::{MultipartOptions, receive_body},
+};
+
+/// Parses a graphql request from a body read with the `PayloadConfig` limit of the resource
+///
+/// Failures are answered like graphql errors, so clients can handle them the same way.
+pub async fn read_request(
+    request: &HttpRequest,
+    body: Result<Bytes, actix_web::Error>,
+) -> Result<Request, HttpResponse> {
+    let body = match body {
+        Ok(body) => body,
+        Err(error) if error.as_response_error().status_code() == StatusCode::PAYLOAD_TOO_LARGE => {
+            return Err(error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "PAYLOAD_TOO_LARGE",
+                "Request body too large".to_string(),
+            ));
+        }
+        Err(error) => return Err(error.error_response()),
+    };
+    let content_type = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    receive_body(content_type, body.as_ref(), MultipartOptions::default())
+        .await
+        .map_err(|error| error_response(StatusCode::BAD_REQUEST, "BAD_REQUEST", error.to_string()))
+}
+
+fn error_response(status: StatusCode, code: &str, message: String) -> HttpResponse {
+    let mut error = ServerError::new(message, None);
+    let mut extensions = ErrorExtensionValues::default();
+    extensions.set("code", code);
+    error.extensions = Some(extensions);
+    HttpResponse::build(status).json(Response::from_errors(vec![error]))
+}
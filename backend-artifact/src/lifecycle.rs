@@ -0,0 +1,131 @@
+use std::{
+    future, io,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use actix_web::{
+    HttpResponse,
+    dev::Server,
+    get,
+    rt::{
+        self,
+        signal::{
+            ctrl_c,
+            unix::{SignalKind, signal},
+        },
+        time::sleep,
+    },
+    web::Data,
+};
+use log::{error, info, warn};
+
+/// Shared state of the process lifecycle, reported by `/ready`
+pub struct Lifecycle {
+    ready: AtomicBool,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self {
+            ready: AtomicBool::new(true),
+        }
+    }
+}
+
+impl Lifecycle {
+    fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+}
+
+#[get("/ready")]
+pub async fn ready(lifecycle: Data<Lifecycle>) -> HttpResponse {
+    if lifecycle.is_ready() {
+        HttpResponse::Ok().body("Ok")
+    } else {
+        HttpResponse::ServiceUnavailable().body("Shutting down")
+    }
+}
+
+/// Runs the servers until a termination signal arrives or one of them stops.
+///
+/// On a signal readiness is reported as false first, after `shutdown_delay` the api server
+/// drains its in-flight requests (bounded by the shutdown timeout of the server) and then the
+/// mgmt server and the optional https redirect server stop. If one of the servers fails, the
+/// others are stopped as well.
+pub async fn run(
+    lifecycle: &Lifecycle,
+    api_server: Server,
+    mgmt_server: Server,
+    redirect_server: Option<Server>,
+    shutdown_delay: Duration,
+) -> io::Result<()> {
+    let api_handle = api_server.handle();
+    let mgmt_handle = mgmt_server.handle();
+    let redirect_handle = redirect_server.as_ref().map(Server::handle);
+    let mut api_task = rt::spawn(api_server);
+    let mut mgmt_task = rt::spawn(mgmt_server);
+    let mut redirect_task = redirect_server.map(rt::spawn);
+    let redirect_stopped = async {
+        match &mut redirect_task {
+            Some(task) => task.await,
+            None => future::pending().await,
+        }
+    };
+
+    let result = tokio::select! {
+        signal = shutdown_signal() => {
+            // without signal handlers there is no orderly shutdown later on, so it happens now
+            lifecycle.ready.store(false, Ordering::SeqCst);
+            if !shutdown_delay.is_zero() {
+                info!("Not ready anymore, waiting {shutdown_delay:?} before shutdown");
+                sleep(shutdown_delay).await;
+            }
+            info!("Draining api server");
+            api_handle.stop(true).await;
+            signal
+        }
+        result = &mut api_task => {
+            warn!("Api server stopped, shutting down");
+            lifecycle.ready.store(false, Ordering::SeqCst);
+            flatten(result)
+        }
+        result = &mut mgmt_task => {
+            warn!("Mgmt server stopped, shutting down");
+            lifecycle.ready.store(false, Ordering::SeqCst);
+            api_handle.stop(true).await;
+            flatten(result)
+        }
+        result = redirect_stopped => {
+            warn!("Https redirect server stopped, shutting down");
+            lifecycle.ready.store(false, Ordering::SeqCst);
+            api_handle.stop(true).await;
+            flatten(result)
+        }
+    };
+    mgmt_handle.stop(true).await;
+    if let Some(redirect_handle) = redirect_handle {
+        redirect_handle.stop(true).await;
+    }
+    if let Err(error) = &result {
+        error!("Shut down after error: {error}");
+    }
+    result
+}
+
+fn flatten(result: Result<io::Result<()>, rt::task::JoinError>) -> io::Result<()> {
+    result.unwrap_or_else(|error| Err(io::Error::other(error)))
+}
+
+async fn shutdown_signal() -> io::Result<()> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        _ = terminate.recv() => info!("Received SIGTERM"),
+        result = ctrl_c() => {
+            result?;
+            info!("Received SIGINT");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::{
+    env,
+    fs::{self, DirBuilder, Permissions},
+    io,
+    net::TcpListener,
+    os::unix::{
+        fs::{DirBuilderExt, FileTypeExt, PermissionsExt},
+        net::UnixListener,
+    },
+    path::Path,
+    process,
+};
+
+use listenfd::ListenFd;
+use log::info;
+
+use backend_impl::config::{ListenAddress, SocketMode};
+
+pub enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+/// Sockets passed by a supervisor through the `LISTEN_FDS` socket activation protocol
+///
+/// The sockets are assigned by the names in `LISTEN_FDNAMES` (`api` and `mgmt`), other
+/// sockets by position: the first one serves the api, the second one the mgmt port.
+/// An inherited socket replaces the configured listeners of its server, it can be a tcp
+/// or a unix stream socket.
+#[derive(Default)]
+pub struct InheritedListeners {
+    api: Option<Listener>,
+    mgmt: Option<Listener>,
+}
+
+impl InheritedListeners {
+    pub fn from_env() -> io::Result<Self> {
+        let mut fds = ListenFd::from_env();
+        let names: Vec<String> = env::var("LISTEN_FDNAMES")
+            .map(|names| names.split(':').map(str::to_string).collect())
+            .unwrap_or_default();
+        let mut listeners = Self::default();
+        for index in 0..fds.len() {
+            let Some(listener) = take_listener(&mut fds, index)? else {
+                continue;
+            };
+            let name = names.get(index).map(String::as_str);
+            let slot = match name {
+                Some("api") => &mut listeners.api,
+                Some("mgmt") => &mut listeners.mgmt,
+                _ if index == 0 => &mut listeners.api,
+                _ if index == 1 => &mut listeners.mgmt,
+                _ => {
+                    return Err(io::Error::other(format!(
+                        "Cannot assign inherited socket {index} ({})",
+                        name.unwrap_or_default()
+                    )));
+                }
+            };
+            *slot = Some(listener);
+        }
+        Ok(listeners)
+    }
+
+    pub fn api(
+        &mut self,
+        addresses: &[ListenAddress],
+        mode: Option<SocketMode>,
+    ) -> io::Result<Vec<Listener>> {
+        take_or_bind("api", &mut self.api, addresses, mode)
+    }
+
+    pub fn mgmt(
+        &mut self,
+        addresses: &[ListenAddress],
+        mode: Option<SocketMode>,
+    ) -> io::Result<Vec<Listener>> {
+        take_or_bind("mgmt", &mut self.mgmt, addresses, mode)
+    }
+}
+
+fn take_listener(fds: &mut ListenFd, index: usize) -> io::Result<Option<Listener>> {
+    // listenfd checks the family of the socket and leaves the fd in place on a mismatch
+    if let Ok(listener) = fds.take_tcp_listener(index) {
+        return Ok(listener.map(Listener::Tcp));
+    }
+    match fds.take_unix_listener(index) {
+        Ok(listener) => Ok(listener.map(Listener::Unix)),
+        Err(error) => Err(io::Error::other(format!(
+            "Inherited socket {index} is no tcp or unix stream socket: {error}"
+        ))),
+    }
+}
+
+fn take_or_bind(
+    name: &str,
+    inherited: &mut Option<Listener>,
+    addresses: &[ListenAddress],
+    mode: Option<SocketMode>,
+) -> io::Result<Vec<Listener>> {
+    if let Some(listener) = inherited.take() {
+        match &listener {
+            Listener::Tcp(listener) => {
+                info!("Using inherited {name} socket {}", listener.local_addr()?)
+            }
+            Listener::Unix(listener) => {
+                info!("Using inherited {name} socket {:?}", listener.local_addr()?)
+            }
+        }
+        return Ok(vec![listener]);
+    }
+    addresses
+        .iter()
+        .map(|address| {
+            info!("Binding {name} socket {address}");
+            bind(address, mode)
+        })
+        .collect()
+}
+
+fn bind(address: &ListenAddress, mode: Option<SocketMode>) -> io::Result<Listener> {
+    match address {
+        ListenAddress::Tcp(address) => TcpListener::bind(address).map(Listener::Tcp),
+        ListenAddress::Unix(path) => {
+            // a socket left over from a previous run blocks the bind
+            if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+                fs::remove_file(path)?;
+            }
+            let listener = match mode {
+                Some(mode) => bind_unix_with_mode(path, mode)?,
+                None => UnixListener::bind(path)?,
+            };
+            Ok(Listener::Unix(listener))
+        }
+    }
+}
+
+/// Binds the socket in a private directory and moves it into place once it has its mode,
+/// a socket bound at its path would be reachable with the umask permissions for a moment.
+fn bind_unix_with_mode(path: &Path, mode: SocketMode) -> io::Result<UnixListener> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{path:?} is no socket path"),
+        )
+    })?;
+    let mut private_name = file_name.to_os_string();
+    private_name.push(format!(".{}.tmp", process::id()));
+    let private_dir = path.with_file_name(private_name);
+    DirBuilder::new().mode(0o700).create(&private_dir)?;
+    let result = (|| {
+        let private_path = private_dir.join(file_name);
+        let listener = UnixListener::bind(&private_path)?;
+        fs::set_permissions(&private_path, Permissions::from_mode(mode.bits()))?;
+        fs::rename(&private_path, path)?;
+        Ok(listener)
+    })();
+    let _ = fs::remove_dir_all(&private_dir);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unix_socket_gets_mode_before_it_appears() {
+        let dir = env::temp_dir().join(format!("listeners-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("api.sock");
+        let mode = SocketMode::try_from("0600".to_string()).unwrap();
+
+        let listener = bind(&ListenAddress::Unix(path.clone()), Some(mode)).unwrap();
+
+        assert!(matches!(listener, Listener::Unix(_)));
+        let metadata = fs::symlink_metadata(&path).unwrap();
+        assert!(metadata.file_type().is_socket());
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use actix_web::{
+    HttpResponse, get, put,
+    web::{Data, Json},
+};
+use serde::Deserialize;
+
+use backend_impl::logging::{LogControl, LogFilterState, MAX_REVERT_AFTER};
+
+#[derive(Deserialize, Debug)]
+pub struct LogFilterUpdate {
+    /// env_logger syntax, e.g. `info,backend_impl=debug`
+    filter: String,
+    /// restore the previous filter after this many seconds, at most a day
+    #[serde(default)]
+    revert_after_seconds: Option<u64>,
+}
+
+#[get("/log-filter")]
+pub async fn get_log_filter(control: Data<LogControl>) -> Json<LogFilterState> {
+    Json(control.current())
+}
+
+#[put("/log-filter")]
+pub async fn put_log_filter(
+    control: Data<LogControl>,
+    update: Json<LogFilterUpdate>,
+) -> HttpResponse {
+    let revert_after = update
+        .revert_after_seconds
+        .map(|seconds| Duration::from_secs(seconds).min(MAX_REVERT_AFTER));
+    match control.update(&update.filter, revert_after) {
+        Ok(state) => HttpResponse::Ok().json(state),
+        Err(error) => HttpResponse::BadRequest().body(error.to_string()),
+    }
+}
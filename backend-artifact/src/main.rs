@@ -1,43 +1,87 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    env,
+    net::{IpAddr, SocketAddr},
+    process::ExitCode,
+    time::Duration,
+};
 
 use actix_4_jwt_auth::{
     DecodedInfo, OIDCValidationError, Oidc, OidcBiscuitValidator, OidcConfig,
     biscuit::{Validation, ValidationOptions},
 };
 use actix_web::{
-    App, HttpServer, get,
+    App, Either, HttpRequest, HttpResponse, HttpServer, get,
     guard::Post,
-    middleware::Logger,
-    web::{Data, resource},
+    http::KeepAlive,
+    middleware::{Logger, from_fn},
+    web::{Bytes, Data, JsonConfig, PayloadConfig, post, resource, scope, to},
 };
 use actix_web_prometheus::PrometheusMetricsBuilder;
-use actix_web_static_files::ResourceFiles;
-use async_graphql::futures_util::future::join_all;
-use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use env_logger::Env;
+use async_graphql_actix_web::GraphQLResponse;
+use clap::Parser;
 use log::{error, info, trace};
 use prometheus::{HistogramVec, histogram_opts};
-use static_files::Resource;
 use thiserror::Error;
 use tracing_actix_web::TracingLogger;
 
 use backend_impl::{
-    AnonymousGraphqlSchema, GraphqlSchema, config::CONFIG, context::UserInfo,
-    create_anonymous_schema, create_schema,
+    AnonymousGraphqlSchema, GraphqlSchema, anonymous_schema_builder,
+    config::{self, ConfigLoadError, ListenAddress},
+    context::UserInfo,
+    logging, schema_builder,
+};
+
+use crate::{
+    assets::Assets,
+    build_info::{SERVER_INFO, register_build_info},
+    cli::{Cli, Command},
+    client_log::ClientLogSink,
+    config_watch::{apply_log_filter, watch_config},
+    cors::cors,
+    graphql_request::read_request,
+    lifecycle::Lifecycle,
+    listeners::{InheritedListeners, Listener},
+    runtime_config::RuntimeConfig,
+    security_headers::security_headers,
 };
 
+mod assets;
+mod build_info;
+mod cli;
+mod client_log;
+mod commands;
+mod config_watch;
+mod cors;
+mod graphql_request;
+mod lifecycle;
+mod listeners;
+mod log_filter;
+mod pwa;
+mod runtime_config;
+mod security_headers;
+mod tls;
+
+#[cfg(feature = "embed-frontend")]
 include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 
+/// upper limit for a batch of frontend log entries
+const CLIENT_LOG_MAX_BODY: usize = 64 * 1024;
+
 async fn graphql(
     context: Data<ApplicationContext>,
     user: Option<DecodedInfo>,
-    request: GraphQLRequest,
-) -> GraphQLResponse {
+    http_request: HttpRequest,
+    body: Result<Bytes, actix_web::Error>,
+) -> Either<GraphQLResponse, HttpResponse> {
     //let user: Option<AuthenticatedUser<UserInfo>> = Some(user);
     trace!("Execute Authenticated: {user:#?}");
     let schema = &context.schema;
     let histogram = context.graphql_request_histogram.clone();
-    let request = request.into_inner();
+    let request = match read_request(&http_request, body).await {
+        Ok(request) => request,
+        Err(response) => return Either::Right(response),
+    };
     let found_user = if let Some(DecodedInfo { jwt: _jwt, payload }) = user {
         match serde_json::from_value::<UserInfo>(payload.private.clone()) {
             Ok(user) => Some(user),
@@ -66,22 +110,26 @@ async fn graphql(
 
     let response = schema.execute(request).await;
     timer.stop_and_record();
-    response.into()
+    Either::Left(response.into())
 }
 async fn graphql_anonymous(
     context: Data<ApplicationContext>,
-    request: GraphQLRequest,
-) -> GraphQLResponse {
+    http_request: HttpRequest,
+    body: Result<Bytes, actix_web::Error>,
+) -> Either<GraphQLResponse, HttpResponse> {
     let schema = &context.anonymous_schema;
     let histogram = context.graphql_request_histogram.clone();
-    let request = request.into_inner();
+    let request = match read_request(&http_request, body).await {
+        Ok(request) => request,
+        Err(response) => return Either::Right(response),
+    };
     let timer = histogram
         .with_label_values(&[request.operation_name.as_deref().unwrap_or_default(), ""])
         .start_timer();
 
     let response = schema.execute(request).await;
     timer.stop_and_record();
-    response.into()
+    Either::Left(response.into())
 }
 
 #[get("/health")]
@@ -97,7 +145,7 @@ struct ApplicationContext {
 }
 
 #[derive(Error, Debug)]
-enum BackendError {
+pub enum BackendError {
     #[error("An IO Error happened {0}")]
     IO(#[from] std::io::Error),
     #[error("An Error from prometheus {0}")]
@@ -106,15 +154,67 @@ enum BackendError {
     ActixWebPrometheus(#[from] actix_web_prometheus::error::Error),
     #[error("Error on OIDC Validation {0}")]
     OidcValidationError(#[from] OIDCValidationError),
+    #[error("Invalid log filter {0}")]
+    LogFilter(#[from] logging::ParseError),
+    #[error("{0}")]
+    Config(Box<ConfigLoadError>),
+    #[error("Cannot watch configuration {0}")]
+    ConfigWatch(#[from] config::WatchError),
+    #[error("Cannot serialize {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    Tls(#[from] tls::TlsError),
+    #[error("{0}")]
+    Assets(#[from] assets::AssetsError),
+}
+
+impl From<ConfigLoadError> for BackendError {
+    fn from(error: ConfigLoadError) -> Self {
+        BackendError::Config(Box::new(error))
+    }
 }
 
-#[actix_web::main]
-async fn main() -> Result<(), BackendError> {
-    env_logger::init_from_env(Env::default().filter_or("LOG_LEVEL", "debug"));
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command() {
+        Command::Serve => actix_web::rt::System::new().block_on(serve(&cli)),
+        Command::PrintSchema { schema } => {
+            commands::print_schema(schema);
+            Ok(())
+        }
+        Command::CheckConfig => commands::check_config(&cli.config_location()),
+        Command::Healthcheck { timeout } => {
+            commands::healthcheck(&cli.config_location(), Duration::from_secs(timeout))
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn serve(cli: &Cli) -> Result<(), BackendError> {
+    let log_control =
+        logging::init(&env::var("LOG_LEVEL").unwrap_or_else(|_| "debug".to_string()))?;
+    let settings = config::init(&cli.config_location())?;
+    apply_log_filter(&settings, &log_control);
 
-    let bind_addr = CONFIG.server_bind_address();
-    let api_port = CONFIG.server_port();
-    let mgmt_port = CONFIG.server_mgmt_port();
+    let bind_addr = settings.server().bind_address();
+    let api_listen = settings.server().listen();
+    let socket_mode = settings.server().unix_socket_mode();
+    let shutdown_timeout = settings.server().shutdown_grace_period().as_secs();
+    let keep_alive = settings
+        .server()
+        .keep_alive()
+        .map_or(KeepAlive::Disabled, KeepAlive::Timeout);
+    let graphql_limit = settings.server().graphql_max_body();
+    let graphql_anonymous_limit = settings.server().graphql_anonymous_max_body();
+    let mut inherited_listeners = InheritedListeners::from_env()?;
+    let tls_settings = settings.server().tls();
+    let tls_config = tls_settings.map(tls::server_config).transpose()?;
 
     let mut labels = HashMap::new();
     labels.insert("server".to_string(), "api".to_string());
@@ -129,11 +229,18 @@ async fn main() -> Result<(), BackendError> {
 
     let registry = prometheus.registry.clone();
     registry.register(Box::new(graphql_request_histogram.clone()))?;
+    let client_log_sink = Data::new(ClientLogSink::new(&registry)?);
+    register_build_info(&registry)?;
+    watch_config(&registry, log_control.clone())?;
+    info!(
+        "Version {} ({}), built {}",
+        SERVER_INFO.version, SERVER_INFO.git_sha, SERVER_INFO.build_time
+    );
 
-    let schema = create_schema();
-    let anonymous_schema = create_anonymous_schema();
+    let schema = schema_builder().data(log_control.clone()).finish();
+    let anonymous_schema = anonymous_schema_builder().data(SERVER_INFO).finish();
 
-    let issuer = CONFIG.auth_issuer().to_string();
+    let issuer = settings.auth().issuer().to_string();
     info!("Issuer: {issuer}");
     let oidc = Oidc::new(OidcConfig::Issuer(issuer.clone().into())).await?;
 
@@ -147,44 +254,92 @@ async fn main() -> Result<(), BackendError> {
     let data = Data::new(ApplicationContext {
         graphql_request_histogram,
         schema,
-        anonymous_schema,
+        anonymous_schema: anonymous_schema.clone(),
     });
-    let main_server = HttpServer::new(move || {
-        let resources: HashMap<&str, Resource> = generate();
-
+    let cors_settings = settings.cors().clone();
+    let base_path = settings.server().base_path();
+    if !base_path.is_empty() {
+        info!("Serving below {base_path}");
+    }
+    let assets = Data::new(Assets::new(
+        settings.frontend(),
+        RuntimeConfig::new(anonymous_schema),
+    )?);
+    let mut main_server = HttpServer::new(move || {
         App::new()
             .wrap(prometheus.clone())
             .wrap(TracingLogger::default())
             .wrap(Logger::default())
+            .wrap(from_fn(security_headers))
+            .app_data(assets.clone())
             .app_data(data.clone())
             .app_data(oidc.clone())
+            .app_data(client_log_sink.clone())
             .service(
-                resource("/graphql")
-                    .guard(Post())
-                    .wrap(biscuit_validator.clone())
-                    .to(graphql),
-            )
-            .service(
-                resource("/graphql_anonymous")
-                    .guard(Post())
-                    .to(graphql_anonymous),
-            )
-            // workaround for proxy troubles
-            .service(
-                resource("/graphql/")
-                    .guard(Post())
-                    .wrap(biscuit_validator.clone())
-                    .to(graphql),
-            )
-            .service(
-                resource("/graphql_anonymous/")
-                    .guard(Post())
-                    .to(graphql_anonymous),
+                scope(&base_path)
+                    .service(
+                        resource("/graphql")
+                            .wrap(biscuit_validator.clone())
+                            .wrap(cors(cors_settings.graphql()))
+                            .app_data(PayloadConfig::new(graphql_limit))
+                            .route(post().to(graphql))
+                            .default_service(to(assets::api_fallback)),
+                    )
+                    .service(
+                        resource("/graphql_anonymous")
+                            .wrap(cors(cors_settings.graphql_anonymous()))
+                            .app_data(PayloadConfig::new(graphql_anonymous_limit))
+                            .route(post().to(graphql_anonymous))
+                            .default_service(to(assets::api_fallback)),
+                    )
+                    // workaround for proxy troubles
+                    .service(
+                        resource("/graphql/")
+                            .wrap(biscuit_validator.clone())
+                            .wrap(cors(cors_settings.graphql()))
+                            .app_data(PayloadConfig::new(graphql_limit))
+                            .route(post().to(graphql))
+                            .default_service(to(assets::api_fallback)),
+                    )
+                    .service(
+                        resource("/graphql_anonymous/")
+                            .wrap(cors(cors_settings.graphql_anonymous()))
+                            .app_data(PayloadConfig::new(graphql_anonymous_limit))
+                            .route(post().to(graphql_anonymous))
+                            .default_service(to(assets::api_fallback)),
+                    )
+                    .service(
+                        resource("/client_log")
+                            .guard(Post())
+                            // navigator.sendBeacon posts its payload as text/plain
+                            .app_data(
+                                JsonConfig::default()
+                                    .limit(CLIENT_LOG_MAX_BODY)
+                                    .content_type_required(false),
+                            )
+                            .to(client_log::client_log),
+                    )
+                    .default_service(to(assets::serve)),
             )
-            .service(ResourceFiles::new("/", resources).resolve_not_found_to_root())
-    })
-    .bind((bind_addr, api_port))?
-    .run();
+    });
+    // unix sockets are meant for a local proxy and always speak plain http
+    for listener in inherited_listeners.api(&api_listen, socket_mode)? {
+        main_server = match (listener, &tls_config) {
+            (Listener::Tcp(listener), Some(tls_config)) => {
+                main_server.listen_rustls_0_23(listener, tls_config.clone())?
+            }
+            (Listener::Tcp(listener), None) => main_server.listen(listener)?,
+            (Listener::Unix(listener), _) => main_server.listen_uds(listener)?,
+        };
+    }
+    let main_server = main_server
+        .workers(settings.server().workers())
+        .max_connections(settings.server().max_connections())
+        .client_request_timeout(settings.server().client_request_timeout())
+        .keep_alive(keep_alive)
+        .disable_signals()
+        .shutdown_timeout(shutdown_timeout)
+        .run();
     let mut labels = HashMap::new();
     labels.insert("server".to_string(), "mgmt".to_string());
 
@@ -194,17 +349,82 @@ async fn main() -> Result<(), BackendError> {
         .endpoint("/metrics")
         .build()
         .unwrap();
-    let mgmt_server = HttpServer::new(move || App::new().wrap(prometheus.clone()).service(health))
-        .bind((bind_addr, mgmt_port))?
-        .workers(2)
-        .run();
-    if let Some(e) = join_all(vec![main_server, mgmt_server])
-        .await
-        .into_iter()
-        .flat_map(|r| r.err())
-        .next()
-    {
-        return Err(e.into());
+    let log_control = Data::new(log_control);
+    let lifecycle = Data::new(Lifecycle::default());
+    let mgmt_lifecycle = lifecycle.clone();
+    let mut mgmt_server = HttpServer::new(move || {
+        App::new()
+            .wrap(prometheus.clone())
+            .app_data(log_control.clone())
+            .app_data(mgmt_lifecycle.clone())
+            .service(health)
+            .service(lifecycle::ready)
+            .service(build_info::info)
+            .service(log_filter::get_log_filter)
+            .service(log_filter::put_log_filter)
+    });
+    let mgmt_tls_config = tls_config.filter(|_| tls_settings.is_some_and(|tls| tls.mgmt()));
+    for listener in inherited_listeners.mgmt(&settings.server().mgmt_listen(), socket_mode)? {
+        mgmt_server = match (listener, &mgmt_tls_config) {
+            (Listener::Tcp(listener), Some(tls_config)) => {
+                mgmt_server.listen_rustls_0_23(listener, tls_config.clone())?
+            }
+            (Listener::Tcp(listener), None) => mgmt_server.listen(listener)?,
+            (Listener::Unix(listener), _) => mgmt_server.listen_uds(listener)?,
+        };
     }
+    let mgmt_server = mgmt_server
+        .workers(settings.server().mgmt_workers())
+        .client_request_timeout(settings.server().client_request_timeout())
+        .keep_alive(keep_alive)
+        .disable_signals()
+        .shutdown_timeout(shutdown_timeout)
+        .run();
+    let api_tcp: Vec<SocketAddr> = api_listen
+        .iter()
+        .filter_map(|address| match address {
+            ListenAddress::Tcp(address) => Some(*address),
+            ListenAddress::Unix(_) => None,
+        })
+        .collect();
+    // redirects to the port of the first tcp listener
+    let https_port = api_tcp
+        .first()
+        .map_or_else(|| settings.server().port(), SocketAddr::port);
+    let redirect_server = match tls_settings.and_then(|tls| tls.redirect_port()) {
+        Some(redirect_port) => {
+            let mut redirect_server = HttpServer::new(move || {
+                App::new().default_service(to(move |request: HttpRequest| async move {
+                    tls::redirect_to_https(&request, https_port)
+                }))
+            });
+            // on the interfaces of the api, `bind_address` if it only listens on unix sockets
+            let mut ips: Vec<IpAddr> = api_tcp.iter().map(SocketAddr::ip).collect();
+            ips.sort();
+            ips.dedup();
+            if ips.is_empty() {
+                ips.push(bind_addr);
+            }
+            for ip in ips {
+                redirect_server = redirect_server.bind(SocketAddr::new(ip, redirect_port))?;
+            }
+            Some(
+                redirect_server
+                    .workers(1)
+                    .disable_signals()
+                    .shutdown_timeout(shutdown_timeout)
+                    .run(),
+            )
+        }
+        None => None,
+    };
+    lifecycle::run(
+        &lifecycle,
+        main_server,
+        mgmt_server,
+        redirect_server,
+        settings.server().shutdown_delay(),
+    )
+    .await?;
     Ok(())
 }
@@ -0,0 +1,42 @@
+use actix_web::{HttpResponse, http::header::CACHE_CONTROL};
+use serde_json::json;
+
+use backend_impl::config;
+
+const SERVICE_WORKER: &str = include_str!("service_worker.js");
+
+/// Web app manifest named after `client.title`, urls are relative to the base path
+pub fn manifest() -> HttpResponse {
+    let settings = config::current();
+    let title = settings.client().title();
+    let manifest = json!({
+        "name": title,
+        "short_name": title,
+        "start_url": "./",
+        "scope": "./",
+        "display": "standalone",
+        "background_color": "#ffffff",
+        "theme_color": "#0066cc",
+        "icons": [{"src": "icon.svg", "sizes": "any", "type": "image/svg+xml"}],
+    });
+    HttpResponse::Ok()
+        .content_type("application/manifest+json")
+        .insert_header((CACHE_CONTROL, "no-cache"))
+        .body(manifest.to_string())
+}
+
+/// Service worker caching `index.html` and the `shell` files for offline use
+///
+/// Browsers install the worker again whenever its script changes, a new `version` therefore
+/// replaces the cache of the previous build.
+pub fn service_worker(version: &str, shell: &[String]) -> HttpResponse {
+    let script = format!(
+        "const VERSION = {};\nconst SHELL = {};\n\n{SERVICE_WORKER}",
+        json!(version),
+        json!(shell)
+    );
+    HttpResponse::Ok()
+        .content_type("text/javascript")
+        .insert_header((CACHE_CONTROL, "no-cache"))
+        .body(script)
+}
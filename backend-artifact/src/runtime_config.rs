@@ -0,0 +1,43 @@
+use log::warn;
+
+use backend_impl::{AnonymousGraphqlSchema, SETTINGS_QUERY};
+
+/// `id` of the script tag, read by yew-app
+const ELEMENT_ID: &str = "runtime-config";
+
+/// Public client configuration embedded into `index.html`
+///
+/// Saves the frontend the `Settings` round-trip before it can start the login. It is
+/// rendered per request, so reloaded configuration files apply to the next page load.
+pub struct RuntimeConfig {
+    schema: AnonymousGraphqlSchema,
+}
+
+impl RuntimeConfig {
+    pub fn new(schema: AnonymousGraphqlSchema) -> Self {
+        Self { schema }
+    }
+
+    /// `<script type="application/json">` with the response, `None` if the query failed
+    ///
+    /// Data blocks are not executed, the Content-Security-Policy needs no hash for them.
+    pub async fn script_tag(&self) -> Option<String> {
+        let response = self.schema.execute(SETTINGS_QUERY).await;
+        if response.is_err() {
+            warn!("Cannot render runtime config: {:?}", response.errors);
+            return None;
+        }
+        let json = match serde_json::to_string(&response.data) {
+            Ok(json) => json,
+            Err(error) => {
+                warn!("Cannot serialize runtime config: {error}");
+                return None;
+            }
+        };
+        // `</script>` in a value must not end the tag
+        let json = json.replace('<', "\\u003c");
+        Some(format!(
+            "<script type=\"application/json\" id=\"{ELEMENT_ID}\">{json}</script>"
+        ))
+    }
+}
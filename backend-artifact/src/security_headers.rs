@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{
+        CONTENT_SECURITY_POLICY, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue, REFERRER_POLICY,
+        STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+    },
+    middleware::Next,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use log::warn;
+use sha2::{Digest, Sha256};
+
+use backend_impl::config;
+
+/// `sha256-...` CSP sources of the inline scripts in `index.html`
+///
+/// trunk bootstraps the wasm module with an inline script, the hash allows it without
+/// `'unsafe-inline'`. [crate::assets] attaches them to the extensions of `index.html` responses.
+pub struct ScriptHashes(Vec<String>);
+
+impl ScriptHashes {
+    pub fn of_html(html: &[u8]) -> Self {
+        let html = String::from_utf8_lossy(html);
+        let mut hashes = Vec::new();
+        let mut rest = html.as_ref();
+        while let Some(start) = rest.find("<script") {
+            rest = &rest[start..];
+            let Some(tag_end) = rest.find('>') else {
+                break;
+            };
+            let tag = &rest[..tag_end];
+            rest = &rest[tag_end + 1..];
+            let Some(script_end) = rest.find("</script>") else {
+                break;
+            };
+            if !tag.contains(" src=") {
+                let digest = Sha256::digest(&rest[..script_end]);
+                hashes.push(format!("'sha256-{}'", STANDARD.encode(digest)));
+            }
+            rest = &rest[script_end..];
+        }
+        Self(hashes)
+    }
+}
+
+/// Adds the headers configured in `security_headers` unless the handler set them
+///
+/// The Content-Security-Policy only applies to documents, so it is added to html responses only.
+pub async fn security_headers(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let mut response = next.call(request).await?;
+    let settings = config::current();
+    let security = settings.security_headers();
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    let hashes = response
+        .response()
+        .extensions()
+        .get::<Arc<ScriptHashes>>()
+        .cloned();
+    let script_hashes = hashes
+        .as_ref()
+        .map_or(&[][..], |hashes| hashes.0.as_slice());
+    let headers = response.headers_mut();
+    insert(headers, X_CONTENT_TYPE_OPTIONS, Some("nosniff"));
+    insert(
+        headers,
+        STRICT_TRANSPORT_SECURITY,
+        security.strict_transport_security(),
+    );
+    insert(headers, REFERRER_POLICY, security.referrer_policy());
+    insert(headers, X_FRAME_OPTIONS, security.frame_options());
+    if is_html {
+        insert(
+            headers,
+            CONTENT_SECURITY_POLICY,
+            settings.content_security_policy(script_hashes).as_deref(),
+        );
+    }
+    Ok(response)
+}
+
+fn insert(headers: &mut HeaderMap, name: HeaderName, value: Option<&str>) {
+    let Some(value) = value else {
+        return;
+    };
+    if headers.contains_key(&name) {
+        return;
+    }
+    match HeaderValue::from_str(value) {
+        Ok(value) => {
+            headers.insert(name, value);
+        }
+        Err(error) => warn!("Invalid value for {name}: {error}"),
+    }
+}
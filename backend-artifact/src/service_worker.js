@@ -0,0 +1,49 @@
+// VERSION and SHELL are prepended by the backend, see pwa.rs
+const CACHE = `shell-${VERSION}`;
+
+self.addEventListener("install", (event) => {
+  event.waitUntil(
+    caches
+      .open(CACHE)
+      .then((cache) => cache.addAll(["./", "index.html", ...SHELL]))
+      .then(() => self.skipWaiting()),
+  );
+});
+
+// drops the shells of previous builds
+self.addEventListener("activate", (event) => {
+  event.waitUntil(
+    caches
+      .keys()
+      .then((names) =>
+        Promise.all(
+          names
+            .filter((name) => name.startsWith("shell-") && name !== CACHE)
+            .map((name) => caches.delete(name)),
+        ),
+      )
+      .then(() => self.clients.claim()),
+  );
+});
+
+self.addEventListener("fetch", (event) => {
+  const request = event.request;
+  if (request.method !== "GET") {
+    return;
+  }
+  if (request.mode === "navigate") {
+    // fresh pages pick up new builds, the cached one works offline
+    event.respondWith(
+      fetch(request).catch(() =>
+        caches.match("index.html", { cacheName: CACHE }),
+      ),
+    );
+    return;
+  }
+  // the shell files are named by their content hash and never change
+  event.respondWith(
+    caches
+      .match(request, { cacheName: CACHE })
+      .then((cached) => cached || fetch(request)),
+  );
+});
@@ -0,0 +1,295 @@
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::{Arc, mpsc},
+    thread,
+    time::Duration,
+};
+
+use actix_web::{HttpRequest, HttpResponse, http::header::LOCATION};
+use arc_swap::ArcSwap;
+use log::{error, info, warn};
+use notify::{Event, RecursiveMode, Watcher};
+use rustls::{
+    ClientConfig, DigitallySignedStruct, ServerConfig, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, ring, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, pem::PemObject},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use thiserror::Error;
+
+use backend_impl::config::TlsSettings;
+
+/// Changes arriving within this time are applied together, certificate and key are
+/// usually replaced one after the other
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Cannot read {}: {source}", path.display())]
+    Pem {
+        path: PathBuf,
+        source: rustls::pki_types::pem::Error,
+    },
+    #[error("No certificate in {}", .0.display())]
+    NoCertificate(PathBuf),
+    #[error("Invalid certificate or key: {0}")]
+    Rustls(#[from] rustls::Error),
+    #[error("Cannot watch certificate: {0}")]
+    Watch(#[from] notify::Error),
+}
+
+/// Server configuration picking up renewed certificates without a restart
+pub fn server_config(settings: &TlsSettings) -> Result<ServerConfig, TlsError> {
+    let provider = Arc::new(ring::default_provider());
+    let resolver = Arc::new(ReloadingCertificate::load(settings, provider.clone())?);
+    resolver.clone().watch()?;
+    Ok(ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_cert_resolver(resolver))
+}
+
+/// Client for `healthcheck`, which probes the own mgmt listener and therefore accepts any
+/// certificate
+pub fn healthcheck_client_config() -> Result<Arc<ClientConfig>, TlsError> {
+    let provider = Arc::new(ring::default_provider());
+    let verifier = Arc::new(AnyCertificate(provider.clone()));
+    Ok(Arc::new(
+        ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier)
+            .with_no_client_auth(),
+    ))
+}
+
+/// Checks the handshake signatures but trusts every certificate
+#[derive(Debug)]
+struct AnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[derive(Debug)]
+struct ReloadingCertificate {
+    certificate: PathBuf,
+    private_key: PathBuf,
+    provider: Arc<CryptoProvider>,
+    current: ArcSwap<CertifiedKey>,
+}
+
+impl ReloadingCertificate {
+    fn load(settings: &TlsSettings, provider: Arc<CryptoProvider>) -> Result<Self, TlsError> {
+        let certificate = settings.certificate().to_path_buf();
+        let private_key = settings.private_key().to_path_buf();
+        let current = certified_key(&certificate, &private_key, &provider)?;
+        info!("Loaded certificate {}", certificate.display());
+        Ok(Self {
+            certificate,
+            private_key,
+            provider,
+            current: ArcSwap::from_pointee(current),
+        })
+    }
+
+    fn reload(&self) {
+        match certified_key(&self.certificate, &self.private_key, &self.provider) {
+            Ok(key) => {
+                self.current.store(Arc::new(key));
+                info!("Reloaded certificate {}", self.certificate.display());
+            }
+            Err(error) => error!("Keeping previous certificate: {error}"),
+        }
+    }
+
+    /// Watches the directories of certificate and key, see `config::watch`
+    fn watch(self: Arc<Self>) -> notify::Result<()> {
+        let files = [&self.certificate, &self.private_key];
+        let names: BTreeSet<OsString> = files
+            .iter()
+            .filter_map(|file| file.file_name().map(OsString::from))
+            .collect();
+        let directories: BTreeSet<&Path> = files
+            .iter()
+            .map(|file| match file.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            })
+            .collect();
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let _ = sender.send(event);
+        })?;
+        for directory in directories {
+            watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        }
+
+        thread::spawn(move || {
+            // moved into the thread to keep it running
+            let _watcher = watcher;
+            while let Ok(event) = receiver.recv() {
+                let concerns_certificate = match &event {
+                    Ok(event) => event.paths.iter().any(|path| {
+                        path.file_name().is_some_and(|name| {
+                            names.contains(name) || name.to_string_lossy().starts_with("..")
+                        })
+                    }),
+                    Err(error) => {
+                        warn!("Error watching certificate: {error}");
+                        false
+                    }
+                };
+                if concerns_certificate {
+                    while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+                    self.reload();
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertificate {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.load_full())
+    }
+}
+
+fn certified_key(
+    certificate: &Path,
+    private_key: &Path,
+    provider: &CryptoProvider,
+) -> Result<CertifiedKey, TlsError> {
+    let pem_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| TlsError::Pem { path, source }
+    };
+    let chain = CertificateDer::pem_file_iter(certificate)
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .map_err(pem_error(certificate))?;
+    if chain.is_empty() {
+        return Err(TlsError::NoCertificate(certificate.to_path_buf()));
+    }
+    let key = PrivateKeyDer::from_pem_file(private_key).map_err(pem_error(private_key))?;
+    let key = CertifiedKey::new(chain, provider.key_provider.load_private_key(key)?);
+    key.keys_match()?;
+    Ok(key)
+}
+
+/// Permanent redirect to the same host and path on the https port
+pub fn redirect_to_https(request: &HttpRequest, https_port: u16) -> HttpResponse {
+    let path = request
+        .uri()
+        .path_and_query()
+        .map_or("/", |path| path.as_str());
+    let location = https_location(request.connection_info().host(), https_port, path);
+    HttpResponse::PermanentRedirect()
+        .insert_header((LOCATION, location))
+        .finish()
+}
+
+fn https_location(host: &str, https_port: u16, path: &str) -> String {
+    let host = host_without_port(host);
+    // a bare ipv6 address needs brackets to be part of a url
+    let host = if host.contains(':') && !host.starts_with('[') {
+        format!("[{host}]")
+    } else {
+        host.to_string()
+    };
+    if https_port == 443 {
+        format!("https://{host}{path}")
+    } else {
+        format!("https://{host}:{https_port}{path}")
+    }
+}
+
+/// `[::1]:80` gives `[::1]`, `example.com:80` gives `example.com`
+fn host_without_port(host: &str) -> &str {
+    match host.rsplit_once(':') {
+        // colons in the name belong to an ipv6 address, a port only follows it in brackets
+        Some((name, port))
+            if port.bytes().all(|b| b.is_ascii_digit())
+                && (!name.contains(':') || name.ends_with(']')) =>
+        {
+            name
+        }
+        _ => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn port_is_removed_from_host() {
+        assert_eq!(host_without_port("example.com:8080"), "example.com");
+        assert_eq!(host_without_port("example.com"), "example.com");
+        assert_eq!(host_without_port("127.0.0.1:80"), "127.0.0.1");
+        assert_eq!(host_without_port("[::1]:80"), "[::1]");
+        assert_eq!(host_without_port("[2001:db8::1]"), "[2001:db8::1]");
+        assert_eq!(host_without_port("[2001:db8::1]:8443"), "[2001:db8::1]");
+        assert_eq!(host_without_port("::1"), "::1");
+    }
+
+    #[test]
+    fn location_keeps_path_and_brackets_ipv6() {
+        assert_eq!(
+            https_location("example.com:8080", 443, "/a?b=c"),
+            "https://example.com/a?b=c"
+        );
+        assert_eq!(
+            https_location("example.com", 8443, "/"),
+            "https://example.com:8443/"
+        );
+        assert_eq!(https_location("[::1]:80", 8443, "/"), "https://[::1]:8443/");
+        assert_eq!(https_location("::1", 8443, "/"), "https://[::1]:8443/");
+    }
+}
@@ -1,60 +1,1018 @@
-use std::net::IpAddr;
+use std::{
+    collections::BTreeMap,
+    env,
+    net::{IpAddr, SocketAddr},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    thread,
+    time::Duration,
+};
 
-use config::{Config, ConfigError, Environment, File};
-use lazy_static::lazy_static;
-use serde::Deserialize;
+use arc_swap::ArcSwap;
+use async_graphql::{Enum, SimpleObject};
+use config::{Config, ConfigError, Environment, File, Map};
+use log::{info, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-#[derive(Deserialize)]
+pub use cors::{CorsMethod, CorsOrigin};
+pub use listen::{ListenAddress, SocketMode};
+pub use notify::Error as WatchError;
+pub use reference::{json_schema, markdown_reference};
+pub use secret::Secret;
+pub use watch::{ReloadError, Reloaded, reload, watch};
+
+mod cors;
+mod csp;
+mod listen;
+mod reference;
+mod secret;
+mod watch;
+
+/// Configuration of the backend
+///
+/// ```yaml
+/// auth:
+///   client_id: rust-fullstack
+///   issuer: http://localhost:8082/realms/rust-test
+/// server:
+///   port: 8080
+/// ```
+///
+/// Every value can be overridden by an environment variable `APP__<SECTION>__<KEY>`,
+/// e.g. `APP__SERVER__PORT=9090`.
+///
+/// Any value can be read from a file instead, e.g. a mounted secret, by appending `_file` to
+/// its key: `client_secret_file: /run/secrets/client_secret` or
+/// `APP__AUTH__CLIENT_SECRET_FILE=/run/secrets/client_secret`.
+///
+/// Changes of the files are applied while running, except for `server`, `cors`, `frontend`
+/// and `auth.issuer` which require a restart.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct Settings {
-    auth_client_id: String,
-    auth_issuer: String,
-    auth_token_url: Option<String>,
+    auth: AuthSettings,
+    #[serde(default)]
+    server: ServerSettings,
+    #[serde(default)]
+    client_log: ClientLogSettings,
+    #[serde(default)]
+    log: LogSettings,
+    #[serde(default)]
+    client: ClientSettings,
+    #[serde(default)]
+    cors: CorsSettings,
+    #[serde(default)]
+    security_headers: SecurityHeaderSettings,
+    #[serde(default)]
+    frontend: FrontendSettings,
+    /// Feature flags by name
+    #[serde(default)]
+    features: BTreeMap<String, bool>,
+}
+
+/// OpenID Connect provider used to authenticate users
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AuthSettings {
+    /// Client id the frontend uses to log in
+    client_id: String,
+    /// Issuer url, tokens of other issuers are rejected
+    issuer: String,
+    /// Token endpoint, defaults to `<issuer>/protocol/openid-connect/token`
+    token_url: Option<String>,
+    /// Authorization endpoint, defaults to `<issuer>/protocol/openid-connect/auth`
     auth_url: Option<String>,
+    /// Realm role required for administrative operations, defaults to `admin`
+    admin_role: Option<String>,
+    /// Secret of a confidential client for calls of the backend to the identity provider,
+    /// never sent to the frontend. Best read from a file with `client_secret_file`
+    client_secret: Option<Secret>,
+}
 
-    server_port: Option<u16>,
-    server_mgmt_port: Option<u16>,
-    server_bind_address: Option<IpAddr>,
+/// Listeners and lifecycle of the http servers
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ServerSettings {
+    /// Port of the api and the frontend, defaults to 8080
+    port: Option<u16>,
+    /// Port for health, metrics and other operational endpoints, defaults to `port` + 1000
+    mgmt_port: Option<u16>,
+    /// Address to bind both ports to, defaults to `::`
+    bind_address: Option<IpAddr>,
+    /// Api listeners like `0.0.0.0:8080`, `[::]:8080` or `unix:/run/app/api.sock`, comma separated
+    /// in the environment. Defaults to `bind_address` and `port`
+    #[serde(default)]
+    listen: Vec<ListenAddress>,
+    /// Mgmt listeners, defaults to `bind_address` and `mgmt_port`
+    #[serde(default)]
+    mgmt_listen: Vec<ListenAddress>,
+    /// Permissions of unix sockets in octal like `"0660"`, defaults to the umask of the process
+    unix_socket_mode: Option<SocketMode>,
+    /// Seconds in-flight requests get to finish on shutdown, defaults to 30
+    shutdown_grace_period: Option<u64>,
+    /// Seconds between reporting not ready and stopping to accept connections, defaults to 0
+    shutdown_delay: Option<u64>,
+    /// Serve https instead of plain http
+    tls: Option<TlsSettings>,
+    /// Worker threads of the api server, defaults to the number of CPU cores
+    workers: Option<usize>,
+    /// Worker threads of the mgmt server, defaults to 2
+    mgmt_workers: Option<usize>,
+    /// Seconds a client gets to send the request head, 0 disables the timeout, defaults to 5
+    client_request_timeout: Option<u64>,
+    /// Seconds idle connections are kept open, 0 disables keep-alive, defaults to 5
+    keep_alive: Option<u64>,
+    /// Concurrent connections per api worker, defaults to 25000
+    max_connections: Option<usize>,
+    /// Bytes accepted in a request to `/graphql`, defaults to 1 MiB
+    graphql_max_body: Option<usize>,
+    /// Bytes accepted in a request to `/graphql_anonymous`, defaults to 16 KiB
+    graphql_anonymous_max_body: Option<usize>,
+    /// Path the app is served under like `/tools/fullstack`, defaults to `/`
+    base_path: Option<String>,
+    /// Honor `X-Forwarded-Prefix` of a reverse proxy stripping its own path prefix, enable only
+    /// behind a proxy which sets or removes the header. Defaults to false
+    #[serde(default)]
+    trust_forwarded_prefix: bool,
+    /// Take the client address from `Forwarded` or `X-Forwarded-For`, e.g. for the rate limit of
+    /// `/client_log`. Enable only behind a proxy which sets these headers. Defaults to false
+    #[serde(default)]
+    trust_forwarded_for: bool,
+}
+
+/// Certificates are reloaded when the files change
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TlsSettings {
+    /// PEM file with the certificate chain, leaf first
+    certificate: PathBuf,
+    /// PEM file with the private key
+    private_key: PathBuf,
+    /// Use TLS on the mgmt port as well, defaults to false
+    #[serde(default)]
+    mgmt: bool,
+    /// Port of a plain http listener redirecting to https, on the addresses of the tcp `listen`
+    /// entries, none by default
+    redirect_port: Option<u16>,
+}
+
+/// Log entries reported by the frontend
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ClientLogSettings {
+    /// Entries accepted per client and minute, defaults to 60
+    rate_limit: Option<u32>,
+}
+
+/// Logging of the backend
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LogSettings {
+    /// Log filter like `info,backend_impl=debug`, replaces the one from `LOG_LEVEL` while set
+    filter: Option<String>,
+}
+
+/// Cross origin access for frontends hosted elsewhere, denied without a policy
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CorsSettings {
+    /// Policy of `/graphql`
+    graphql: Option<CorsPolicy>,
+    /// Policy of `/graphql_anonymous`
+    graphql_anonymous: Option<CorsPolicy>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CorsPolicy {
+    /// Origins like `https://app.example.com`, `*` allows any origin unless `allow_credentials`
+    allowed_origins: Vec<CorsOrigin>,
+    /// Methods allowed in requests, defaults to `POST`
+    #[serde(default)]
+    allowed_methods: Vec<CorsMethod>,
+    /// Headers allowed in requests, defaults to `authorization`, `content-type` and the
+    /// `x-request-id`, `traceparent`, `x-client-name` and `x-client-version` headers yew-app sends
+    #[serde(default)]
+    allowed_headers: Vec<String>,
+    /// Allow cookies and authorization headers, defaults to false
+    #[serde(default)]
+    allow_credentials: bool,
+    /// Seconds browsers may cache a preflight response, not cached by default
+    max_age: Option<usize>,
+}
+
+/// Headers added to every api response, an empty value omits the header
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SecurityHeaderSettings {
+    /// `Strict-Transport-Security`, defaults to `max-age=31536000; includeSubDomains`
+    strict_transport_security: Option<String>,
+    /// `Referrer-Policy`, defaults to `strict-origin-when-cross-origin`
+    referrer_policy: Option<String>,
+    /// `X-Frame-Options`, defaults to `DENY`
+    frame_options: Option<String>,
+    /// `Content-Security-Policy`, replaces the one derived from `auth` and `client`
+    content_security_policy: Option<String>,
+    /// Further sources for `connect-src` of the derived policy, e.g. `https://api.example.com`
+    #[serde(default)]
+    connect_src: Vec<String>,
+}
+
+/// Delivery of the frontend
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FrontendSettings {
+    /// Serve the frontend from this directory instead of the files embedded at build time,
+    /// e.g. `yew-app/dist` while `trunk watch` rebuilds it. Required by builds without the
+    /// `embed-frontend` feature
+    dir: Option<PathBuf>,
+    /// Reload open pages whenever files in `dir` change, defaults to false
+    #[serde(default)]
+    live_reload: bool,
+}
+
+/// Branding of the frontend, served by the anonymous `clientConfig` query
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ClientSettings {
+    /// Application name in the masthead and the document title, defaults to `Rust Fullstack`
+    title: Option<String>,
+    /// Logo in the masthead instead of the title
+    logo_url: Option<String>,
+    /// Links to documentation or support shown in the masthead
+    #[serde(default)]
+    support_links: Vec<SupportLink>,
+    /// Banner above the page naming the environment, e.g. `STAGING`, none by default
+    environment: Option<EnvironmentBanner>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, SimpleObject, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SupportLink {
+    /// Text of the link
+    label: String,
+    url: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, SimpleObject, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EnvironmentBanner {
+    /// Text of the banner, e.g. `STAGING`
+    text: String,
+    /// Background of the banner, defaults to `default`
+    #[serde(default)]
+    color: BannerColor,
+}
+
+/// Colors of the patternfly banner
+#[derive(Deserialize, Serialize, JsonSchema, Enum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BannerColor {
+    #[default]
+    Default,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Teal,
+    Blue,
+    Purple,
 }
 
 impl Settings {
-    pub fn auth_client_id(&self) -> &str {
-        &self.auth_client_id
+    pub fn auth(&self) -> &AuthSettings {
+        &self.auth
+    }
+    pub fn server(&self) -> &ServerSettings {
+        &self.server
+    }
+    pub fn client_log(&self) -> &ClientLogSettings {
+        &self.client_log
+    }
+    pub fn log(&self) -> &LogSettings {
+        &self.log
+    }
+    pub fn client(&self) -> &ClientSettings {
+        &self.client
+    }
+    pub fn cors(&self) -> &CorsSettings {
+        &self.cors
+    }
+    pub fn security_headers(&self) -> &SecurityHeaderSettings {
+        &self.security_headers
+    }
+    pub fn frontend(&self) -> &FrontendSettings {
+        &self.frontend
+    }
+    pub fn feature_enabled(&self, feature: &str) -> bool {
+        self.features.get(feature).copied().unwrap_or(false)
+    }
+    /// keys that differ from `other` and are only applied by a restart
+    pub fn restart_required_changes(&self, other: &Settings) -> Vec<&'static str> {
+        let mut changes = Vec::new();
+        if self.server != other.server {
+            changes.push("server");
+        }
+        if self.cors != other.cors {
+            changes.push("cors");
+        }
+        if self.frontend != other.frontend {
+            changes.push("frontend");
+        }
+        if self.auth.issuer != other.auth.issuer {
+            changes.push("auth.issuer");
+        }
+        changes
+    }
+}
+
+impl AuthSettings {
+    pub fn client_id(&self) -> &str {
+        &self.client_id
     }
-    pub fn auth_issuer(&self) -> &str {
-        &self.auth_issuer
+    pub fn issuer(&self) -> &str {
+        &self.issuer
     }
-    pub fn auth_token_url(&self) -> String {
-        self.auth_token_url
+    pub fn token_url(&self) -> String {
+        self.token_url
             .clone()
-            .unwrap_or_else(|| format!("{}/protocol/openid-connect/token", self.auth_issuer))
+            .unwrap_or_else(|| format!("{}/protocol/openid-connect/token", self.issuer))
     }
     pub fn auth_url(&self) -> String {
         self.auth_url
             .clone()
-            .unwrap_or_else(|| format!("{}/protocol/openid-connect/auth", self.auth_issuer))
+            .unwrap_or_else(|| format!("{}/protocol/openid-connect/auth", self.issuer))
+    }
+    /// realm role required for administrative operations
+    pub fn admin_role(&self) -> &str {
+        self.admin_role.as_deref().unwrap_or("admin")
+    }
+    pub fn client_secret(&self) -> Option<&Secret> {
+        self.client_secret.as_ref()
+    }
+}
+
+impl ServerSettings {
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(8080)
+    }
+    pub fn mgmt_port(&self) -> u16 {
+        self.mgmt_port.unwrap_or_else(|| self.port() + 1000)
+    }
+    pub fn bind_address(&self) -> IpAddr {
+        self.bind_address.unwrap_or_else(|| IpAddr::from([0u8; 16]))
+    }
+    pub fn listen(&self) -> Vec<ListenAddress> {
+        listen_or(&self.listen, self.bind_address(), self.port())
+    }
+    pub fn mgmt_listen(&self) -> Vec<ListenAddress> {
+        listen_or(&self.mgmt_listen, self.bind_address(), self.mgmt_port())
+    }
+    pub fn unix_socket_mode(&self) -> Option<SocketMode> {
+        self.unix_socket_mode
+    }
+    /// time in-flight requests get to finish on shutdown
+    pub fn shutdown_grace_period(&self) -> Duration {
+        Duration::from_secs(self.shutdown_grace_period.unwrap_or(30))
+    }
+    /// time between reporting not ready and stopping to accept connections
+    pub fn shutdown_delay(&self) -> Duration {
+        Duration::from_secs(self.shutdown_delay.unwrap_or(0))
+    }
+    pub fn tls(&self) -> Option<&TlsSettings> {
+        self.tls.as_ref()
+    }
+    pub fn workers(&self) -> usize {
+        self.workers
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get))
+    }
+    pub fn mgmt_workers(&self) -> usize {
+        self.mgmt_workers.unwrap_or(2)
+    }
+    pub fn client_request_timeout(&self) -> Duration {
+        Duration::from_secs(self.client_request_timeout.unwrap_or(5))
+    }
+    /// `None` if keep-alive is disabled
+    pub fn keep_alive(&self) -> Option<Duration> {
+        Some(Duration::from_secs(self.keep_alive.unwrap_or(5))).filter(|d| !d.is_zero())
+    }
+    pub fn max_connections(&self) -> usize {
+        self.max_connections.unwrap_or(25_000)
+    }
+    pub fn graphql_max_body(&self) -> usize {
+        self.graphql_max_body.unwrap_or(1024 * 1024)
+    }
+    pub fn graphql_anonymous_max_body(&self) -> usize {
+        self.graphql_anonymous_max_body.unwrap_or(16 * 1024)
     }
-    pub fn server_port(&self) -> u16 {
-        self.server_port.unwrap_or(8080)
+    /// `base_path` with a leading and without a trailing slash, empty for `/`
+    pub fn base_path(&self) -> String {
+        let path = self
+            .base_path
+            .as_deref()
+            .unwrap_or_default()
+            .trim_matches('/');
+        if path.is_empty() {
+            String::new()
+        } else {
+            format!("/{path}")
+        }
     }
-    pub fn server_mgmt_port(&self) -> u16 {
-        self.server_mgmt_port
-            .unwrap_or_else(|| self.server_port() + 1000)
+    pub fn trust_forwarded_prefix(&self) -> bool {
+        self.trust_forwarded_prefix
     }
-    pub fn server_bind_address(&self) -> IpAddr {
-        self.server_bind_address
-            .unwrap_or_else(|| IpAddr::from([0u8; 16]))
+    pub fn trust_forwarded_for(&self) -> bool {
+        self.trust_forwarded_for
     }
 }
 
-fn create_settings() -> Result<Settings, ConfigError> {
-    let cfg = Config::builder()
-        .add_source(File::with_name("config.yaml"))
-        .add_source(Environment::with_prefix("app"))
-        .build()?;
-    let settings: Settings = cfg.get("oauth")?;
-    Ok(settings)
+fn listen_or(listen: &[ListenAddress], address: IpAddr, port: u16) -> Vec<ListenAddress> {
+    if listen.is_empty() {
+        vec![ListenAddress::Tcp(SocketAddr::new(address, port))]
+    } else {
+        listen.to_vec()
+    }
+}
+
+impl TlsSettings {
+    pub fn certificate(&self) -> &Path {
+        &self.certificate
+    }
+    pub fn private_key(&self) -> &Path {
+        &self.private_key
+    }
+    pub fn mgmt(&self) -> bool {
+        self.mgmt
+    }
+    pub fn redirect_port(&self) -> Option<u16> {
+        self.redirect_port
+    }
+}
+
+impl ClientLogSettings {
+    /// maximum number of client log entries accepted per client and minute
+    pub fn rate_limit(&self) -> u32 {
+        self.rate_limit.unwrap_or(60)
+    }
+}
+
+impl SecurityHeaderSettings {
+    pub fn strict_transport_security(&self) -> Option<&str> {
+        header_or(
+            &self.strict_transport_security,
+            "max-age=31536000; includeSubDomains",
+        )
+    }
+    pub fn referrer_policy(&self) -> Option<&str> {
+        header_or(&self.referrer_policy, "strict-origin-when-cross-origin")
+    }
+    pub fn frame_options(&self) -> Option<&str> {
+        header_or(&self.frame_options, "DENY")
+    }
+}
+
+fn header_or<'a>(value: &'a Option<String>, default: &'a str) -> Option<&'a str> {
+    Some(value.as_deref().unwrap_or(default)).filter(|value| !value.is_empty())
 }
 
-lazy_static! {
-    pub static ref CONFIG: Settings = create_settings().expect("Cannot load config.yaml");
+impl CorsSettings {
+    pub fn graphql(&self) -> Option<&CorsPolicy> {
+        self.graphql.as_ref()
+    }
+    pub fn graphql_anonymous(&self) -> Option<&CorsPolicy> {
+        self.graphql_anonymous.as_ref()
+    }
+}
+
+impl CorsPolicy {
+    pub fn allowed_origins(&self) -> &[CorsOrigin] {
+        &self.allowed_origins
+    }
+    pub fn allowed_methods(&self) -> Vec<&str> {
+        if self.allowed_methods.is_empty() {
+            vec!["POST"]
+        } else {
+            self.allowed_methods
+                .iter()
+                .map(CorsMethod::as_str)
+                .collect()
+        }
+    }
+    pub fn allowed_headers(&self) -> Vec<&str> {
+        or_default(
+            &self.allowed_headers,
+            &[
+                "authorization",
+                "content-type",
+                "x-request-id",
+                "traceparent",
+                "x-client-name",
+                "x-client-version",
+            ],
+        )
+    }
+    pub fn allow_credentials(&self) -> bool {
+        self.allow_credentials
+    }
+    pub fn max_age(&self) -> Option<usize> {
+        self.max_age
+    }
+    /// browsers refuse credentials for any origin, so the combination is rejected
+    fn validate(&self, key: &str) -> Result<(), ConfigError> {
+        if self.allow_credentials && self.allowed_origins.contains(&CorsOrigin::Any) {
+            return Err(ConfigError::Message(format!(
+                "`*` in `allowed_origins` cannot be combined with credentials, list the origins \
+                 for key `{key}.allow_credentials`"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl CorsSettings {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let policies = [
+            ("cors.graphql", &self.graphql),
+            ("cors.graphql_anonymous", &self.graphql_anonymous),
+        ];
+        for (key, policy) in policies {
+            if let Some(policy) = policy {
+                policy.validate(key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn or_default<'a>(values: &'a [String], default: &[&'static str]) -> Vec<&'a str> {
+    if values.is_empty() {
+        default.to_vec()
+    } else {
+        values.iter().map(String::as_str).collect()
+    }
+}
+
+impl ClientSettings {
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or("Rust Fullstack")
+    }
+    pub fn logo_url(&self) -> Option<&str> {
+        self.logo_url.as_deref()
+    }
+    pub fn support_links(&self) -> &[SupportLink] {
+        &self.support_links
+    }
+    pub fn environment(&self) -> Option<&EnvironmentBanner> {
+        self.environment.as_ref()
+    }
+}
+
+impl FrontendSettings {
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+    pub fn live_reload(&self) -> bool {
+        self.live_reload
+    }
+}
+
+impl LogSettings {
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+}
+
+/// Flat layout below `oauth:`, still accepted for existing deployments
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LegacySettings {
+    auth_client_id: String,
+    auth_issuer: String,
+    auth_token_url: Option<String>,
+    auth_url: Option<String>,
+    auth_admin_role: Option<String>,
+
+    server_port: Option<u16>,
+    server_mgmt_port: Option<u16>,
+    server_bind_address: Option<IpAddr>,
+    server_trust_forwarded_for: Option<bool>,
+    server_shutdown_grace_period: Option<u64>,
+    server_shutdown_delay: Option<u64>,
+
+    client_log_rate_limit: Option<u32>,
+}
+
+impl From<LegacySettings> for Settings {
+    fn from(legacy: LegacySettings) -> Self {
+        Settings {
+            auth: AuthSettings {
+                client_id: legacy.auth_client_id,
+                issuer: legacy.auth_issuer,
+                token_url: legacy.auth_token_url,
+                auth_url: legacy.auth_url,
+                admin_role: legacy.auth_admin_role,
+                client_secret: None,
+            },
+            server: ServerSettings {
+                port: legacy.server_port,
+                mgmt_port: legacy.server_mgmt_port,
+                bind_address: legacy.server_bind_address,
+                listen: Vec::new(),
+                mgmt_listen: Vec::new(),
+                unix_socket_mode: None,
+                shutdown_grace_period: legacy.server_shutdown_grace_period,
+                shutdown_delay: legacy.server_shutdown_delay,
+                tls: None,
+                workers: None,
+                mgmt_workers: None,
+                client_request_timeout: None,
+                keep_alive: None,
+                max_connections: None,
+                graphql_max_body: None,
+                graphql_anonymous_max_body: None,
+                base_path: None,
+                trust_forwarded_prefix: false,
+                trust_forwarded_for: legacy.server_trust_forwarded_for.unwrap_or(false),
+            },
+            client_log: ClientLogSettings {
+                rate_limit: legacy.client_log_rate_limit,
+            },
+            log: LogSettings::default(),
+            client: ClientSettings::default(),
+            cors: CorsSettings::default(),
+            security_headers: SecurityHeaderSettings::default(),
+            frontend: FrontendSettings::default(),
+            features: BTreeMap::new(),
+        }
+    }
+}
+
+/// Where to find the configuration
+#[derive(Debug, Clone)]
+pub struct ConfigLocation {
+    /// base configuration file
+    pub path: PathBuf,
+    /// overrides from `<name>.<profile>.<extension>` next to the base file, e.g. `config.prod.yaml`
+    pub profile: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigLoadError {
+    #[error("Cannot load configuration {}: {source}", files.join(", "))]
+    Load {
+        files: Vec<String>,
+        source: ConfigError,
+    },
+    #[error("Configuration already initialized")]
+    AlreadyInitialized,
+}
+
+impl ConfigLocation {
+    /// all files in the order they are applied
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = vec![self.path.clone()];
+        if let Some(profile) = &self.profile {
+            files.push(profile_path(&self.path, profile));
+        }
+        files
+    }
+
+    pub fn load(&self) -> Result<Settings, ConfigLoadError> {
+        self.load_with_environment(env::vars().collect())
+    }
+
+    fn load_with_environment(
+        &self,
+        variables: Map<String, String>,
+    ) -> Result<Settings, ConfigLoadError> {
+        let files = self.files();
+        let error = |source| ConfigLoadError::Load {
+            files: files.iter().map(|f| f.display().to_string()).collect(),
+            source,
+        };
+        let mut builder = Config::builder().add_source(File::from(self.path.as_path()));
+        for profile_file in &files[1..] {
+            if profile_file.exists() {
+                info!("Applying profile {}", profile_file.display());
+            } else {
+                warn!("Profile {} not found", profile_file.display());
+            }
+            builder = builder.add_source(File::from(profile_file.as_path()).required(false));
+        }
+        let cfg = builder
+            .add_source(environment(variables))
+            .build()
+            .map_err(error)?;
+        let cfg = Config::builder()
+            .add_source(secret::FileReferences(cfg))
+            .build()
+            .map_err(error)?;
+        let settings: Settings = if cfg.get_table("oauth").is_ok() {
+            warn!("Configuration below `oauth:` is deprecated, move it to `auth:` and `server:`");
+            let legacy: LegacySettings = cfg.get("oauth").map_err(error)?;
+            legacy.into()
+        } else {
+            cfg.try_deserialize().map_err(error)?
+        };
+        settings.cors.validate().map_err(error)?;
+        Ok(settings)
+    }
+}
+
+/// `APP__SERVER__PORT` overrides `server.port`, lists like `server.listen` are comma separated
+fn environment(variables: Map<String, String>) -> Environment {
+    Environment::with_prefix("app")
+        .prefix_separator("__")
+        .separator("__")
+        .try_parsing(true)
+        .list_separator(",")
+        .with_list_parse_key("server.listen")
+        .with_list_parse_key("server.mgmt_listen")
+        .source(Some(variables))
+}
+
+fn profile_path(path: &Path, profile: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}.{profile}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{profile}"),
+    };
+    path.with_file_name(name)
+}
+
+struct LoadedConfig {
+    location: ConfigLocation,
+    settings: ArcSwap<Settings>,
+}
+
+static CONFIG: OnceLock<LoadedConfig> = OnceLock::new();
+
+/// Loads the configuration and makes it available through [current]
+pub fn init(location: &ConfigLocation) -> Result<Arc<Settings>, ConfigLoadError> {
+    let settings = location.load()?;
+    CONFIG
+        .set(LoadedConfig {
+            location: location.clone(),
+            settings: ArcSwap::from_pointee(settings),
+        })
+        .map_err(|_| ConfigLoadError::AlreadyInitialized)?;
+    Ok(current())
+}
+
+/// The configuration loaded by [init], replaced on every successful [reload]
+pub fn current() -> Arc<Settings> {
+    loaded().settings.load_full()
+}
+
+fn loaded() -> &'static LoadedConfig {
+    CONFIG.get().expect("configuration not initialized")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn profile_overrides_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "config.yaml",
+            "auth:\n  client_id: app\n  issuer: http://base\nserver:\n  port: 8080\n",
+        );
+        write(
+            dir.path(),
+            "config.prod.yaml",
+            "auth:\n  issuer: http://prod\n",
+        );
+        let settings = ConfigLocation {
+            path,
+            profile: Some("prod".into()),
+        }
+        .load()
+        .unwrap();
+        assert_eq!(settings.auth().issuer(), "http://prod");
+        assert_eq!(settings.auth().client_id(), "app");
+        assert_eq!(settings.server().port(), 8080);
+        assert_eq!(settings.server().mgmt_port(), 9080);
+    }
+
+    #[test]
+    fn legacy_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "config.yaml",
+            "oauth:\n  auth_client_id: app\n  auth_issuer: http://legacy\n  server_port: 8000\n",
+        );
+        let settings = ConfigLocation {
+            path,
+            profile: None,
+        }
+        .load()
+        .unwrap();
+        assert_eq!(settings.auth().issuer(), "http://legacy");
+        assert_eq!(settings.server().port(), 8000);
+    }
+
+    #[test]
+    fn error_names_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "config.yaml",
+            "auth:\n  client_id: app\n  issuer: http://base\nserver:\n  port: eighty\n",
+        );
+        let error = ConfigLocation {
+            path,
+            profile: None,
+        }
+        .load()
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("server.port"), "{error}");
+    }
+
+    #[test]
+    fn cors_policy_is_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let load = |cors: &str| {
+            let path = write(
+                dir.path(),
+                "config.yaml",
+                &format!("auth:\n  client_id: app\n  issuer: http://base\ncors:\n{cors}"),
+            );
+            ConfigLocation {
+                path,
+                profile: None,
+            }
+            .load()
+        };
+        let settings = load(
+            "  graphql:\n    allowed_origins: [\"https://app.example.com:8443\", \"*\"]\n    \
+             allowed_methods: [GET, POST]\n",
+        )
+        .unwrap();
+        let policy = settings.cors().graphql().unwrap();
+        assert_eq!(
+            policy.allowed_origins(),
+            [
+                CorsOrigin::Origin("https://app.example.com:8443".into()),
+                CorsOrigin::Any
+            ]
+        );
+        assert_eq!(policy.allowed_methods(), ["GET", "POST"]);
+
+        for (cors, key) in [
+            (
+                "  graphql:\n    allowed_origins: [\"*\"]\n    allow_credentials: true\n",
+                "cors.graphql.allow_credentials",
+            ),
+            (
+                "  graphql_anonymous:\n    allowed_origins: [\"https://app.example.com/\"]\n",
+                "cors.graphql_anonymous.allowed_origins",
+            ),
+            (
+                "  graphql:\n    allowed_origins: [app.example.com]\n",
+                "cors.graphql.allowed_origins",
+            ),
+            (
+                "  graphql:\n    allowed_origins: [\"*\"]\n    allowed_methods: [post]\n",
+                "cors.graphql.allowed_methods",
+            ),
+        ] {
+            let error = load(cors).unwrap_err().to_string();
+            assert!(error.contains(key), "{error}");
+        }
+    }
+
+    #[test]
+    fn base_path_is_normalized() {
+        let server = |base_path: &str| -> ServerSettings {
+            serde_json::from_value(serde_json::json!({ "base_path": base_path })).unwrap()
+        };
+        assert_eq!(server("/").base_path(), "");
+        assert_eq!(server("tools/fullstack/").base_path(), "/tools/fullstack");
+        assert_eq!(ServerSettings::default().base_path(), "");
+    }
+
+    #[test]
+    fn environment_overrides_nested_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "config.yaml",
+            "auth:\n  client_id: app\n  issuer: http://base\n",
+        );
+        let variables = Map::from([
+            ("APP__AUTH__ISSUER".to_string(), "http://env".to_string()),
+            ("APP__SERVER__MGMT_PORT".to_string(), "9999".to_string()),
+            (
+                "APP__SERVER__LISTEN".to_string(),
+                "127.0.0.1:80,unix:/run/api.sock".to_string(),
+            ),
+            ("APP_CONFIG".to_string(), "ignored.yaml".to_string()),
+        ]);
+        let settings = ConfigLocation {
+            path,
+            profile: None,
+        }
+        .load_with_environment(variables)
+        .unwrap();
+        assert_eq!(settings.auth().issuer(), "http://env");
+        assert_eq!(settings.server().mgmt_port(), 9999);
+        assert_eq!(
+            settings.server().listen(),
+            vec![
+                ListenAddress::Tcp(([127, 0, 0, 1], 80).into()),
+                ListenAddress::Unix("/run/api.sock".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn values_from_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let client_id = write(dir.path(), "client_id", "from-file\n");
+        let issuer = write(dir.path(), "issuer", "http://file");
+        let path = write(
+            dir.path(),
+            "config.yaml",
+            &format!(
+                "auth:\n  client_id: inline\n  client_id_file: {}\n  issuer: http://base\n",
+                client_id.display()
+            ),
+        );
+        let variables = Map::from([(
+            "APP__AUTH__ISSUER_FILE".to_string(),
+            issuer.display().to_string(),
+        )]);
+        let settings = ConfigLocation {
+            path,
+            profile: None,
+        }
+        .load_with_environment(variables)
+        .unwrap();
+        assert_eq!(settings.auth().client_id(), "from-file");
+        assert_eq!(settings.auth().issuer(), "http://file");
+    }
+
+    #[test]
+    fn client_secret_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = write(dir.path(), "client_secret", "hunter2\n");
+        let path = write(
+            dir.path(),
+            "config.yaml",
+            "auth:\n  client_id: app\n  issuer: http://base\n",
+        );
+        let variables = Map::from([(
+            "APP__AUTH__CLIENT_SECRET_FILE".to_string(),
+            secret.display().to_string(),
+        )]);
+        let settings = ConfigLocation {
+            path,
+            profile: None,
+        }
+        .load_with_environment(variables)
+        .unwrap();
+        let client_secret = settings.auth().client_secret().unwrap();
+        assert_eq!(client_secret.expose(), "hunter2");
+        let serialized = serde_json::to_string(&settings).unwrap();
+        assert!(!serialized.contains("hunter2"));
+    }
+
+    #[test]
+    fn restart_required_for_server_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let load = |content: &str| {
+            ConfigLocation {
+                path: write(dir.path(), "config.yaml", content),
+                profile: None,
+            }
+            .load()
+            .unwrap()
+        };
+        let base = load("auth:\n  client_id: app\n  issuer: http://base\n");
+        let live = load(
+            "auth:\n  client_id: other\n  issuer: http://base\nclient_log:\n  rate_limit: 5\n",
+        );
+        let restart = load("auth:\n  client_id: app\n  issuer: http://base\nserver:\n  port: 1\n");
+        assert!(base.restart_required_changes(&live).is_empty());
+        assert_eq!(base.restart_required_changes(&restart), vec!["server"]);
+    }
 }
@@ -0,0 +1,99 @@
+use std::{fmt, str::FromStr};
+
+use http::Method;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+const ANY_ORIGIN: &str = "*";
+const STANDARD_METHODS: [Method; 9] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::HEAD,
+    Method::OPTIONS,
+    Method::CONNECT,
+    Method::PATCH,
+    Method::TRACE,
+];
+
+/// Origin allowed by a CORS policy: `scheme://host[:port]` or `*` for any origin
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+#[schemars(with = "String")]
+pub enum CorsOrigin {
+    Any,
+    Origin(String),
+}
+
+impl TryFrom<String> for CorsOrigin {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value == ANY_ORIGIN {
+            return Ok(CorsOrigin::Any);
+        }
+        let origin = Url::parse(&value)
+            .map_err(|error| format!("`{value}` is no origin: {error}"))?
+            .origin();
+        if !origin.is_tuple() {
+            return Err(format!(
+                "`{value}` is no origin like `https://app.example.com`"
+            ));
+        }
+        let origin = origin.ascii_serialization();
+        if origin != value {
+            // browsers send the serialized form, anything else would never match
+            return Err(format!("`{value}` is no origin, use `{origin}`"));
+        }
+        Ok(CorsOrigin::Origin(origin))
+    }
+}
+
+impl From<CorsOrigin> for String {
+    fn from(origin: CorsOrigin) -> Self {
+        origin.to_string()
+    }
+}
+
+impl fmt::Display for CorsOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorsOrigin::Any => f.write_str(ANY_ORIGIN),
+            CorsOrigin::Origin(origin) => f.write_str(origin),
+        }
+    }
+}
+
+/// Http method allowed by a CORS policy like `POST`
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+#[schemars(with = "String")]
+pub struct CorsMethod(Method);
+
+impl CorsMethod {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl TryFrom<String> for CorsMethod {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let method = Method::from_str(&value).map_err(|_| format!("`{value}` is no method"))?;
+        let upper = value.to_ascii_uppercase();
+        // methods are case sensitive, `post` would never match the `POST` of a browser
+        if upper != value && STANDARD_METHODS.iter().any(|m| m.as_str() == upper) {
+            return Err(format!("`{value}` is no method, use `{upper}`"));
+        }
+        Ok(CorsMethod(method))
+    }
+}
+
+impl From<CorsMethod> for String {
+    fn from(method: CorsMethod) -> Self {
+        method.0.to_string()
+    }
+}
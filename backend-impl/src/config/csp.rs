@@ -0,0 +1,106 @@
+use std::collections::BTreeSet;
+
+use super::Settings;
+
+impl Settings {
+    /// `Content-Security-Policy` of the frontend, `None` if disabled by an empty override
+    ///
+    /// Unless overridden the policy only allows the own origin, plus the identity provider for
+    /// `connect-src` and the logo for `img-src`. `script_hashes` are the `sha256-...` sources
+    /// of the inline scripts in `index.html`.
+    pub fn content_security_policy(&self, script_hashes: &[String]) -> Option<String> {
+        if let Some(policy) = &self.security_headers.content_security_policy {
+            return Some(policy.clone()).filter(|policy| !policy.is_empty());
+        }
+        let auth = self.auth();
+        let mut connect_src: BTreeSet<String> =
+            [auth.issuer(), &auth.auth_url(), &auth.token_url()]
+                .into_iter()
+                .filter_map(origin)
+                .map(str::to_string)
+                .collect();
+        connect_src.extend(self.security_headers.connect_src.iter().cloned());
+        let img_src = self.client().logo_url().and_then(origin);
+        let form_action = origin(&auth.auth_url()).map(str::to_string);
+
+        let mut script_src = vec!["'self'", "'wasm-unsafe-eval'"];
+        script_src.extend(script_hashes.iter().map(|hash| hash.as_str()));
+        let directives = [
+            ("default-src", vec!["'self'"]),
+            ("script-src", script_src),
+            // patternfly components set style attributes
+            ("style-src", vec!["'self'", "'unsafe-inline'"]),
+            (
+                "img-src",
+                [Some("'self'"), Some("data:"), img_src]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            ),
+            ("font-src", vec!["'self'"]),
+            (
+                "connect-src",
+                std::iter::once("'self'")
+                    .chain(connect_src.iter().map(String::as_str))
+                    .collect(),
+            ),
+            (
+                "form-action",
+                std::iter::once("'self'")
+                    .chain(form_action.as_deref())
+                    .collect(),
+            ),
+            ("frame-ancestors", vec!["'none'"]),
+            ("base-uri", vec!["'self'"]),
+            ("object-src", vec!["'none'"]),
+        ];
+        Some(
+            directives
+                .iter()
+                .map(|(name, sources)| format!("{name} {}", sources.join(" ")))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+}
+
+/// `https://host:port` of an absolute url
+fn origin(url: &str) -> Option<&str> {
+    let (scheme, rest) = url.split_once("://")?;
+    if scheme.is_empty() {
+        return None;
+    }
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    Some(&url[..scheme.len() + 3 + end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connect_src_contains_identity_provider() {
+        let settings: Settings = serde_json::from_value(serde_json::json!({
+            "auth": {
+                "client_id": "app",
+                "issuer": "https://sso.example.com/realms/test",
+                "token_url": "https://token.example.com:8443/token",
+            },
+            "security_headers": {"connect_src": ["https://api.example.com"]},
+        }))
+        .unwrap();
+        let policy = settings
+            .content_security_policy(&["'sha256-abc'".to_string()])
+            .unwrap();
+        assert!(
+            policy.contains(
+                "connect-src 'self' https://api.example.com https://sso.example.com https://token.example.com:8443;"
+            ),
+            "{policy}"
+        );
+        assert!(
+            policy.contains("'wasm-unsafe-eval' 'sha256-abc';"),
+            "{policy}"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::{fmt, net::SocketAddr, path::PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+const UNIX_PREFIX: &str = "unix:";
+
+/// Address of a listener: `IP:port` or `unix:/path/to/socket`
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+#[schemars(with = "String")]
+pub enum ListenAddress {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl TryFrom<String> for ListenAddress {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if let Some(path) = value.strip_prefix(UNIX_PREFIX) {
+            return Ok(ListenAddress::Unix(path.into()));
+        }
+        value
+            .parse()
+            .map(ListenAddress::Tcp)
+            .map_err(|_| format!("`{value}` is neither `IP:port` nor `{UNIX_PREFIX}/path`"))
+    }
+}
+
+impl From<ListenAddress> for String {
+    fn from(address: ListenAddress) -> Self {
+        address.to_string()
+    }
+}
+
+impl fmt::Display for ListenAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddress::Tcp(address) => write!(f, "{address}"),
+            ListenAddress::Unix(path) => write!(f, "{UNIX_PREFIX}{}", path.display()),
+        }
+    }
+}
+
+/// Permissions of unix sockets in octal notation like `"0660"`
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+#[schemars(with = "String")]
+pub struct SocketMode(u32);
+
+impl SocketMode {
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+impl TryFrom<String> for SocketMode {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        u32::from_str_radix(&value, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o7777)
+            .map(SocketMode)
+            .ok_or_else(|| format!("`{value}` is no octal file mode like `0660`"))
+    }
+}
+
+impl From<SocketMode> for String {
+    fn from(mode: SocketMode) -> Self {
+        format!("{:04o}", mode.0)
+    }
+}
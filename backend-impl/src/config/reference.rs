@@ -0,0 +1,214 @@
+use std::fmt::Write;
+
+use schemars::schema_for;
+use serde_json::{Map, Value};
+
+use super::Settings;
+
+/// JSON Schema of the configuration file
+pub fn json_schema() -> Value {
+    schema_for!(Settings).to_value()
+}
+
+/// Markdown reference of all configuration keys with their environment variables
+pub fn markdown_reference() -> String {
+    let schema = json_schema();
+    let empty = Map::new();
+    let definitions = schema
+        .get("$defs")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let mut out = String::from("# Configuration reference\n\n");
+    if let Some(description) = schema.get("description").and_then(Value::as_str) {
+        writeln!(out, "{description}\n").unwrap();
+    }
+    write_object(&mut out, &schema, definitions, &[]);
+    out
+}
+
+fn write_object(out: &mut String, schema: &Value, definitions: &Map<String, Value>, path: &[&str]) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let mut rows = Vec::new();
+    let mut sections = Vec::new();
+    let mut properties: Vec<_> = properties.iter().collect();
+    properties.sort_by_key(|(name, _)| name.as_str());
+    for (name, property) in properties {
+        let mut key_path = path.to_vec();
+        key_path.push(name);
+        let (resolved, description) = resolve(property, definitions);
+        if resolved.get("properties").is_some() {
+            sections.push((key_path, resolved, description));
+        } else {
+            rows.push(format!(
+                "| `{}` | {} | {} | `{}` | {} |",
+                key_path.join("."),
+                type_name(resolved, definitions),
+                if required.contains(&name.as_str()) {
+                    "yes"
+                } else {
+                    "no"
+                },
+                environment_variable(&key_path, is_map(resolved)),
+                description.unwrap_or_default().replace('\n', " "),
+            ));
+        }
+    }
+    if !rows.is_empty() {
+        out.push_str("| Key | Type | Required | Environment | Description |\n");
+        out.push_str("|-----|------|----------|-------------|-------------|\n");
+        for row in rows {
+            writeln!(out, "{row}").unwrap();
+        }
+        out.push('\n');
+    }
+    for (key_path, section, description) in sections {
+        writeln!(
+            out,
+            "{} `{}`\n",
+            "#".repeat(key_path.len() + 1),
+            key_path.join(".")
+        )
+        .unwrap();
+        if let Some(description) = description {
+            writeln!(out, "{description}\n").unwrap();
+        }
+        write_object(out, section, definitions, &key_path);
+    }
+}
+
+/// follows `$ref` and optional wrappers, keeps the most specific description
+fn resolve<'a>(
+    schema: &'a Value,
+    definitions: &'a Map<String, Value>,
+) -> (&'a Value, Option<&'a str>) {
+    let description = schema.get("description").and_then(Value::as_str);
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str)
+        && let Some(target) = reference
+            .strip_prefix("#/$defs/")
+            .and_then(|name| definitions.get(name))
+    {
+        let (resolved, inner_description) = resolve(target, definitions);
+        return (resolved, description.or(inner_description));
+    }
+    if let Some(variants) = schema.get("anyOf").and_then(Value::as_array) {
+        let mut not_null = variants
+            .iter()
+            .filter(|v| v.get("type").and_then(Value::as_str) != Some("null"));
+        if let (Some(variant), None) = (not_null.next(), not_null.next()) {
+            let (resolved, inner_description) = resolve(variant, definitions);
+            return (resolved, description.or(inner_description));
+        }
+    }
+    (schema, description)
+}
+
+fn type_name(schema: &Value, definitions: &Map<String, Value>) -> String {
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return values
+            .iter()
+            .map(|v| {
+                format!(
+                    "`{}`",
+                    v.as_str().map_or_else(|| v.to_string(), str::to_string)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+    }
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(single)) => vec![single.as_str()],
+        Some(Value::Array(multiple)) => multiple
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|t| *t != "null")
+            .collect(),
+        _ => Vec::new(),
+    };
+    let name = match types.as_slice() {
+        ["array"] => match schema.get("items") {
+            Some(items) => format!(
+                "list of {}",
+                type_name(resolve(items, definitions).0, definitions)
+            ),
+            None => "list".to_string(),
+        },
+        ["object"] => match schema.get("additionalProperties") {
+            Some(values @ Value::Object(_)) => format!(
+                "map of {}",
+                type_name(resolve(values, definitions).0, definitions)
+            ),
+            _ => "object".to_string(),
+        },
+        [] => "any".to_string(),
+        types => types.join(" or "),
+    };
+    match schema.get("format").and_then(Value::as_str) {
+        Some(format) => format!("{name} ({format})"),
+        None => name,
+    }
+}
+
+fn is_map(schema: &Value) -> bool {
+    schema
+        .get("additionalProperties")
+        .is_some_and(Value::is_object)
+}
+
+/// entries of maps are set by one variable each
+fn environment_variable(key_path: &[&str], map: bool) -> String {
+    let mut variable = String::from("APP");
+    for segment in key_path {
+        variable.push_str("__");
+        variable.push_str(&segment.to_uppercase());
+    }
+    if map {
+        variable.push_str("__<NAME>");
+    }
+    variable
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use super::*;
+
+    /// regenerate with `UPDATE_CONFIG_REFERENCE=1 cargo test -p backend-impl`
+    #[test]
+    fn reference_is_up_to_date() {
+        let docs = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../docs");
+        let schema_path = docs.join("config.schema.json");
+        let reference_path = docs.join("configuration.md");
+        if env::var_os("UPDATE_CONFIG_REFERENCE").is_some() {
+            fs::create_dir_all(&docs).unwrap();
+            let schema = serde_json::to_string_pretty(&json_schema()).unwrap();
+            fs::write(&schema_path, schema + "\n").unwrap();
+            fs::write(&reference_path, markdown_reference()).unwrap();
+            return;
+        }
+        let outdated = |path: &PathBuf| {
+            format!(
+                "{} is outdated, regenerate it with UPDATE_CONFIG_REFERENCE=1 cargo test",
+                path.display()
+            )
+        };
+        // compared as values, the key order depends on the features of serde_json
+        let schema: Value =
+            serde_json::from_str(&fs::read_to_string(&schema_path).unwrap_or_default())
+                .unwrap_or_default();
+        assert!(schema == json_schema(), "{}", outdated(&schema_path));
+        let reference = fs::read_to_string(&reference_path).unwrap_or_default();
+        assert!(
+            reference == markdown_reference(),
+            "{}",
+            outdated(&reference_path)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::{fmt, fs};
+
+use config::{Config, ConfigError, Map, Source, Value, ValueKind};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Suffix of keys which name a file holding the value, e.g. `client_secret_file`
+const FILE_SUFFIX: &str = "_file";
+
+const MASK: &str = "***";
+
+/// Confidential value like a password or client secret
+///
+/// The value is masked in `Debug` and when serialized, it is only available through
+/// [Secret::expose].
+#[derive(Deserialize, JsonSchema, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({MASK})")
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(MASK)
+    }
+}
+
+/// Replaces every `<key>_file: <path>` by `<key>: <content of path>`
+///
+/// Works for all keys and sources, so `APP__AUTH__CLIENT_SECRET_FILE` reads `auth.client_secret`
+/// from a mounted kubernetes or docker secret. The file takes precedence over an inline value.
+#[derive(Debug, Clone)]
+pub(super) struct FileReferences(pub Config);
+
+impl Source for FileReferences {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        resolve(self.0.collect()?, "")
+    }
+}
+
+fn resolve(table: Map<String, Value>, prefix: &str) -> Result<Map<String, Value>, ConfigError> {
+    let mut resolved = Map::new();
+    let mut from_files = Vec::new();
+    for (key, value) in table {
+        let path = format!("{prefix}{key}");
+        if let Some(target) = key.strip_suffix(FILE_SUFFIX)
+            && let ValueKind::String(file) = &value.kind
+        {
+            let content = fs::read_to_string(file).map_err(|error| {
+                ConfigError::Message(format!("Cannot read {path} `{file}`: {error}"))
+            })?;
+            let content = content.trim_end_matches(['\n', '\r']).to_string();
+            from_files.push((target.to_string(), Value::new(Some(file), content)));
+            continue;
+        }
+        let origin = value.origin().map(str::to_string);
+        let value = match value.kind {
+            ValueKind::Table(inner) => {
+                Value::new(origin.as_ref(), resolve(inner, &format!("{path}."))?)
+            }
+            kind => Value::new(origin.as_ref(), kind),
+        };
+        resolved.insert(key, value);
+    }
+    resolved.extend(from_files);
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_is_masked() {
+        let secret: Secret = serde_json::from_str("\"hunter2\"").unwrap();
+        assert_eq!(secret.expose(), "hunter2");
+        assert_eq!(format!("{secret:?}"), "Secret(***)");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"***\"");
+    }
+}
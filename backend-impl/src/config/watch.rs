@@ -0,0 +1,127 @@
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    path::PathBuf,
+    sync::{Arc, mpsc},
+    thread,
+    time::Duration,
+};
+
+use log::{debug, error, info, warn};
+use notify::{Event, RecursiveMode, Watcher};
+use thiserror::Error;
+
+use super::{ConfigLoadError, Settings, loaded};
+
+/// Changes arriving within this time are applied together
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Error, Debug)]
+pub enum ReloadError {
+    #[error(transparent)]
+    Load(Box<ConfigLoadError>),
+    #[error("Changes of {} require a restart", .0.join(", "))]
+    RestartRequired(Vec<&'static str>),
+}
+
+impl From<ConfigLoadError> for ReloadError {
+    fn from(error: ConfigLoadError) -> Self {
+        ReloadError::Load(Box::new(error))
+    }
+}
+
+/// Successful outcome of [reload]
+pub enum Reloaded {
+    Applied {
+        previous: Arc<Settings>,
+        current: Arc<Settings>,
+    },
+    Unchanged,
+}
+
+/// Loads the configuration files again and replaces the current settings
+///
+/// Nothing is replaced if the new configuration is invalid or contains changes which
+/// require a restart.
+pub fn reload() -> Result<Reloaded, ReloadError> {
+    let loaded = loaded();
+    let settings = loaded.location.load()?;
+    let previous = loaded.settings.load_full();
+    if *previous == settings {
+        return Ok(Reloaded::Unchanged);
+    }
+    let changes = previous.restart_required_changes(&settings);
+    if !changes.is_empty() {
+        return Err(ReloadError::RestartRequired(changes));
+    }
+    let current = Arc::new(settings);
+    loaded.settings.store(current.clone());
+    Ok(Reloaded::Applied { previous, current })
+}
+
+/// Watches the configuration files and reloads them on change
+///
+/// The directories are watched instead of the files, so replaced files (editors,
+/// kubernetes config maps) are noticed as well. `on_reload` is called after every attempt.
+pub fn watch(
+    on_reload: impl Fn(&Result<Reloaded, ReloadError>) + Send + 'static,
+) -> notify::Result<()> {
+    let files = loaded().location.files();
+    let names: BTreeSet<OsString> = files
+        .iter()
+        .filter_map(|file| file.file_name().map(OsString::from))
+        .collect();
+    let directories: BTreeSet<PathBuf> = files
+        .iter()
+        .map(|file| match file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        })
+        .collect();
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let _ = sender.send(event);
+    })?;
+    for directory in &directories {
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+    }
+    info!("Watching configuration {files:?}");
+
+    thread::spawn(move || {
+        // moved into the thread to keep it running
+        let _watcher = watcher;
+        while let Ok(event) = receiver.recv() {
+            if !concerns_config(&event, &names) {
+                continue;
+            }
+            while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+            let result = reload();
+            match &result {
+                Ok(Reloaded::Applied { .. }) => info!("Configuration reloaded"),
+                Ok(Reloaded::Unchanged) => debug!("Configuration unchanged"),
+                Err(error @ ReloadError::RestartRequired(_)) => {
+                    warn!("Configuration not reloaded: {error}")
+                }
+                Err(error) => error!("Configuration not reloaded: {error}"),
+            }
+            on_reload(&result);
+        }
+    });
+    Ok(())
+}
+
+/// kubernetes swaps mounted config maps by replacing the `..data` symlink
+fn concerns_config(event: &notify::Result<Event>, names: &BTreeSet<OsString>) -> bool {
+    match event {
+        Ok(event) => event.paths.iter().any(|path| {
+            path.file_name().is_some_and(|name| {
+                names.contains(name) || name.to_string_lossy().starts_with("..")
+            })
+        }),
+        Err(error) => {
+            warn!("Error watching configuration: {error}");
+            false
+        }
+    }
+}
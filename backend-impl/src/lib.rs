@@ -1,7 +1,17 @@
-use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use std::time::Duration;
 
-use crate::config::CONFIG;
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, ErrorExtensions, Object, Schema, SchemaBuilder,
+    SimpleObject,
+};
+use serde::Serialize;
+
+use crate::config::{EnvironmentBanner, SupportLink};
 use crate::context::UserInfo;
+use crate::logging::{LogControl, LogFilterState, MAX_REVERT_AFTER};
+
+/// The query yew-app sends on startup, the backend embeds its response into `index.html`
+pub const SETTINGS_QUERY: &str = include_str!("settings.graphql");
 
 pub fn add(left: usize, right: usize) -> usize {
     left + right
@@ -9,48 +19,150 @@ pub fn add(left: usize, right: usize) -> usize {
 
 pub struct Query;
 pub struct QueryAnonymous;
+pub struct Mutation;
 
 #[Object]
 impl QueryAnonymous {
     /// gives the coordinates for authentication
     async fn authentication(&self) -> AuthenticationData {
+        let settings = config::current();
+        let auth = settings.auth();
         AuthenticationData {
-            client_id: CONFIG.auth_client_id(),
-            auth_url: CONFIG.auth_url(),
-            token_url: CONFIG.auth_token_url(),
+            client_id: auth.client_id().to_string(),
+            auth_url: auth.auth_url(),
+            token_url: auth.token_url(),
+        }
+    }
+    /// title, logo, links and environment banner of the frontend
+    async fn client_config(&self) -> ClientConfig {
+        let settings = config::current();
+        let client = settings.client();
+        ClientConfig {
+            title: client.title().to_string(),
+            logo_url: client.logo_url().map(str::to_string),
+            support_links: client.support_links().to_vec(),
+            environment: client.environment().cloned(),
         }
     }
+    /// describes the build of the running server
+    async fn server_info(&self, ctx: &Context<'_>) -> async_graphql::Result<ServerInfo> {
+        Ok(ctx.data::<ServerInfo>()?.clone())
+    }
 }
 #[Object]
 impl Query {
     /// Returns the sum of a and b
     async fn add(&self, ctx: &Context<'_>, a: i32, b: i32) -> async_graphql::Result<i32> {
-        ctx.data::<UserInfo>()?;
+        require_user(ctx)?;
         Ok(a + b)
     }
+    /// Returns the active log filter, requires the admin role
+    async fn log_filter(&self, ctx: &Context<'_>) -> async_graphql::Result<LogFilterState> {
+        require_admin(ctx)?;
+        Ok(ctx.data::<LogControl>()?.current())
+    }
+}
+#[Object]
+impl Mutation {
+    /// Changes the log filter (env_logger syntax like `info,backend_impl=debug`), requires the admin role.
+    /// The previous filter is restored after `revertAfterSeconds` if given, at most a day.
+    async fn set_log_filter(
+        &self,
+        ctx: &Context<'_>,
+        filter: String,
+        revert_after_seconds: Option<u32>,
+    ) -> async_graphql::Result<LogFilterState> {
+        require_admin(ctx)?;
+        let revert_after = revert_after_seconds
+            .map(|seconds| Duration::from_secs(seconds.into()).min(MAX_REVERT_AFTER));
+        Ok(ctx.data::<LogControl>()?.update(&filter, revert_after)?)
+    }
+}
+
+/// The user of the access token, errors with code `UNAUTHENTICATED` to let clients refresh it
+fn require_user<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a UserInfo> {
+    ctx.data::<UserInfo>().map_err(|_| {
+        async_graphql::Error::new("Not authenticated")
+            .extend_with(|_, extensions| extensions.set("code", "UNAUTHENTICATED"))
+    })
+}
+
+fn require_admin<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a UserInfo> {
+    let user = require_user(ctx)?;
+    if user.has_role(config::current().auth().admin_role()) {
+        Ok(user)
+    } else {
+        Err(async_graphql::Error::new("Admin role required")
+            .extend_with(|_, extensions| extensions.set("code", "FORBIDDEN")))
+    }
 }
 
 #[derive(SimpleObject)]
 struct AuthenticationData {
-    client_id: &'static str,
+    client_id: String,
     token_url: String,
     auth_url: String,
 }
 
-pub type GraphqlSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+#[derive(SimpleObject)]
+struct ClientConfig {
+    title: String,
+    logo_url: Option<String>,
+    support_links: Vec<SupportLink>,
+    environment: Option<EnvironmentBanner>,
+}
+
+/// Build information of the running server, has to be added as schema data
+#[derive(SimpleObject, Serialize, Debug, Clone)]
+pub struct ServerInfo {
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    pub build_time: &'static str,
+    /// hash over the sdl of both graphql schemas
+    pub schema_hash: &'static str,
+    /// hash over the embedded frontend
+    pub assets_hash: &'static str,
+}
+
+pub type GraphqlSchema = Schema<Query, Mutation, EmptySubscription>;
 pub type AnonymousGraphqlSchema = Schema<QueryAnonymous, EmptyMutation, EmptySubscription>;
 
+pub fn schema_builder() -> SchemaBuilder<Query, Mutation, EmptySubscription> {
+    Schema::build(Query, Mutation, EmptySubscription)
+}
+pub fn anonymous_schema_builder() -> SchemaBuilder<QueryAnonymous, EmptyMutation, EmptySubscription>
+{
+    Schema::build(QueryAnonymous, EmptyMutation, EmptySubscription)
+}
 pub fn create_schema() -> GraphqlSchema {
-    Schema::build(Query, EmptyMutation, EmptySubscription).finish()
+    schema_builder().finish()
 }
 pub fn create_anonymous_schema() -> AnonymousGraphqlSchema {
-    Schema::build(QueryAnonymous, EmptyMutation, EmptySubscription).finish()
+    anonymous_schema_builder().finish()
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use async_graphql::{Value, futures_util::FutureExt};
+
+    use super::*;
+
+    #[test]
+    fn missing_user_is_unauthenticated() {
+        let response = create_schema()
+            .execute("{ add(a: 1, b: 2) }")
+            .now_or_never()
+            .unwrap();
+        let code = response.errors[0]
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.get("code"));
+        assert_eq!(code, Some(&Value::from("UNAUTHENTICATED")));
+    }
+}
 
 pub mod config;
+pub mod logging;
 pub mod context {
     use serde::{Deserialize, Serialize};
 
@@ -59,5 +171,21 @@ pub mod context {
         pub name: String,
         pub email: Option<String>,
         pub email_verified: Option<bool>,
+        #[serde(default)]
+        pub realm_access: Option<RealmAccess>,
+    }
+
+    #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+    pub struct RealmAccess {
+        #[serde(default)]
+        pub roles: Vec<String>,
+    }
+
+    impl UserInfo {
+        pub fn has_role(&self, role: &str) -> bool {
+            self.realm_access
+                .as_ref()
+                .is_some_and(|access| access.roles.iter().any(|r| r == role))
+        }
     }
 }
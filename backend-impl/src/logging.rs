@@ -0,0 +1,290 @@
+use std::{
+    sync::{
+        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use async_graphql::SimpleObject;
+use env_filter::Filter;
+pub use env_filter::ParseError;
+use log::{LevelFilter, Log, Metadata, Record, info};
+use serde::Serialize;
+use thiserror::Error;
+
+/// Longest time a changed filter may stay active before it is reverted
+pub const MAX_REVERT_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Handle to read and change the log filter of the running process
+///
+/// Filters use the env_logger syntax, e.g. `info,backend_impl=debug`.
+#[derive(Clone)]
+pub struct LogControl {
+    inner: Arc<LogControlInner>,
+}
+
+struct LogControlInner {
+    state: RwLock<FilterState>,
+    /// filter the process started with, restored when the configuration no longer sets one
+    initial: String,
+    /// incremented on every change, a pending revert only applies if nothing changed in between
+    generation: AtomicU64,
+}
+
+struct FilterState {
+    spec: String,
+    filter: Filter,
+    revert: Option<PendingRevert>,
+}
+
+struct PendingRevert {
+    spec: String,
+    at: Instant,
+}
+
+/// Currently active log filter
+#[derive(SimpleObject, Serialize, Debug, Clone)]
+pub struct LogFilterState {
+    pub filter: String,
+    /// filter which will be restored automatically
+    pub revert_filter: Option<String>,
+    pub revert_in_seconds: Option<u64>,
+}
+
+struct DynamicLogger {
+    logger: env_logger::Logger,
+    control: LogControl,
+}
+
+impl Log for DynamicLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.control.read().filter.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.control.read().filter.matches(record) {
+            self.logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+/// Installs the global logger with the given initial filter
+pub fn init(spec: &str) -> Result<LogControl, ParseError> {
+    let control = LogControl::new(spec)?;
+    log::set_max_level(control.read().filter.filter());
+    // the wrapped logger passes everything, filtering happens in DynamicLogger
+    let logger = env_logger::Builder::new()
+        .filter_level(LevelFilter::Trace)
+        .build();
+    log::set_boxed_logger(Box::new(DynamicLogger {
+        logger,
+        control: control.clone(),
+    }))
+    .expect("logger already initialized");
+    Ok(control)
+}
+
+fn parse_filter(spec: &str) -> Result<Filter, ParseError> {
+    Ok(env_filter::Builder::new().try_parse(spec)?.build())
+}
+
+impl LogControl {
+    fn new(spec: &str) -> Result<Self, ParseError> {
+        Ok(Self {
+            inner: Arc::new(LogControlInner {
+                state: RwLock::new(FilterState {
+                    spec: spec.to_string(),
+                    filter: parse_filter(spec)?,
+                    revert: None,
+                }),
+                initial: spec.to_string(),
+                generation: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, FilterState> {
+        self.inner.state.read().expect("log filter poisoned")
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, FilterState> {
+        self.inner.state.write().expect("log filter poisoned")
+    }
+
+    pub fn current(&self) -> LogFilterState {
+        let state = self.read();
+        let revert = state.revert.as_ref();
+        LogFilterState {
+            filter: state.spec.clone(),
+            revert_filter: revert.map(|r| r.spec.clone()),
+            revert_in_seconds: revert
+                .map(|r| r.at.saturating_duration_since(Instant::now()).as_secs()),
+        }
+    }
+
+    /// Replaces the filter, optionally restoring the previous one after `revert_after`
+    pub fn update(
+        &self,
+        spec: &str,
+        revert_after: Option<Duration>,
+    ) -> Result<LogFilterState, LogFilterError> {
+        let filter = parse_filter(spec)?;
+        let at = revert_after
+            .map(|after| {
+                Instant::now()
+                    .checked_add(after)
+                    .ok_or(LogFilterError::RevertOutOfRange(after))
+            })
+            .transpose()?;
+        let generation = {
+            let mut state = self.write();
+            let revert = at.map(|at| PendingRevert {
+                // reverting twice restores the filter from before the first change
+                spec: state
+                    .revert
+                    .take()
+                    .map(|r| r.spec)
+                    .unwrap_or_else(|| state.spec.clone()),
+                at,
+            });
+            self.replace(&mut state, spec.to_string(), filter, revert)
+        };
+        // logging needs the lock, so not before it is released
+        info!("Log filter changed to {spec}");
+        if let Some(after) = revert_after {
+            let control = self.clone();
+            thread::spawn(move || {
+                thread::sleep(after);
+                control.revert(generation);
+            });
+        }
+        Ok(self.current())
+    }
+
+    /// Applies the filter of the configuration, `None` restores the one the process started with
+    ///
+    /// Nothing changes if the filter is active already.
+    pub fn apply_configured(&self, spec: Option<&str>) -> Result<(), LogFilterError> {
+        let spec = spec.unwrap_or(&self.inner.initial);
+        if self.read().spec != spec {
+            self.update(spec, None)?;
+        }
+        Ok(())
+    }
+
+    /// Restores the filter before the change `generation`, unless it was changed again since
+    fn revert(&self, generation: u64) {
+        let spec = {
+            let mut state = self.write();
+            if self.inner.generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let Some(revert) = state.revert.take() else {
+                return;
+            };
+            match parse_filter(&revert.spec) {
+                Ok(filter) => {
+                    self.replace(&mut state, revert.spec.clone(), filter, None);
+                    Ok(revert.spec)
+                }
+                Err(error) => Err((revert.spec, error)),
+            }
+        };
+        match spec {
+            Ok(spec) => info!("Log filter reverted to {spec}"),
+            Err((spec, error)) => log::error!("Cannot revert log filter to {spec}: {error}"),
+        }
+    }
+
+    /// Activates `filter`, returns the new generation
+    fn replace(
+        &self,
+        state: &mut FilterState,
+        spec: String,
+        filter: Filter,
+        revert: Option<PendingRevert>,
+    ) -> u64 {
+        log::set_max_level(filter.filter());
+        *state = FilterState {
+            spec,
+            filter,
+            revert,
+        };
+        self.inner.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LogFilterError {
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("Cannot revert after {0:?}, the delay is out of range")]
+    RevertOutOfRange(Duration),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn generation(control: &LogControl) -> u64 {
+        control.inner.generation.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn update_remembers_previous_filter() {
+        let control = LogControl::new("info").unwrap();
+        let state = control.update("debug", Some(MINUTE)).unwrap();
+        assert_eq!(state.filter, "debug");
+        assert_eq!(state.revert_filter.as_deref(), Some("info"));
+        let state = control.update("trace", Some(MINUTE)).unwrap();
+        assert_eq!(state.revert_filter.as_deref(), Some("info"));
+    }
+
+    #[test]
+    fn update_rejects_revert_out_of_range() {
+        let control = LogControl::new("info").unwrap();
+        let result = control.update("debug", Some(Duration::from_secs(u64::MAX)));
+        assert!(matches!(result, Err(LogFilterError::RevertOutOfRange(_))));
+        assert_eq!(control.current().filter, "info");
+        assert_eq!(generation(&control), 0);
+    }
+
+    #[test]
+    fn removed_configured_filter_restores_initial() {
+        let control = LogControl::new("info").unwrap();
+        control.apply_configured(None).unwrap();
+        assert_eq!(generation(&control), 0);
+        control.apply_configured(Some("debug")).unwrap();
+        assert_eq!(control.current().filter, "debug");
+        control.apply_configured(None).unwrap();
+        assert_eq!(control.current().filter, "info");
+    }
+
+    #[test]
+    fn revert_restores_previous_filter() {
+        let control = LogControl::new("info").unwrap();
+        control.update("debug", Some(MINUTE)).unwrap();
+        control.revert(generation(&control));
+        let state = control.current();
+        assert_eq!(state.filter, "info");
+        assert_eq!(state.revert_filter, None);
+    }
+
+    #[test]
+    fn superseded_revert_is_ignored() {
+        let control = LogControl::new("info").unwrap();
+        control.update("debug", Some(MINUTE)).unwrap();
+        let superseded = generation(&control);
+        control.update("trace", None).unwrap();
+        control.revert(superseded);
+        assert_eq!(control.current().filter, "trace");
+    }
+}
@@ -1,15 +1,35 @@
-use std::fs;
+use std::{env, fs, process::Command};
 
 use anyhow::Result;
 
-use backend_impl::{create_anonymous_schema, create_schema};
+use backend_impl::{SETTINGS_QUERY, create_anonymous_schema, create_schema};
 
 fn main() -> Result<()> {
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+    println!("cargo:rustc-env=BUILD_GIT_SHA={}", git_sha());
     write_graphql_schema()?;
     write_anonymous_graphql_schema()?;
+    write_settings_query()?;
     Ok(())
 }
 
+/// same as the backend, so client log entries name the build reporting them
+fn git_sha() -> String {
+    if let Ok(sha) = env::var("GIT_SHA") {
+        return sha;
+    }
+    Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|sha| sha.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
 fn write_graphql_schema() -> Result<()> {
     let schema = create_schema();
     fs::write("graphql/schema.graphql", schema.sdl())?;
@@ -20,3 +40,8 @@ fn write_anonymous_graphql_schema() -> Result<()> {
     fs::write("graphql/anonymous_schema.graphql", schema.sdl())?;
     Ok(())
 }
+/// owned by the backend, which answers it ahead of time in `index.html`
+fn write_settings_query() -> Result<()> {
+    fs::write("graphql/settings.graphql", SETTINGS_QUERY)?;
+    Ok(())
+}
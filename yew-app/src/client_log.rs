@@ -0,0 +1,214 @@
+use std::cell::{Cell, RefCell};
+use std::panic::PanicHookInfo;
+
+use lazy_static::lazy_static;
+use log::{Level, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+use wasm_bindgen::closure::Closure;
+
+use crate::location::base_url;
+
+/// Entries are sent at the latest this long after the first one was buffered
+const FLUSH_DELAY_MS: i32 = 5000;
+/// A full batch is sent immediately
+const MAX_BATCH_SIZE: usize = 20;
+/// git sha of the build, the backend reports the same in `serverInfo`
+const VERSION: &str = env!("BUILD_GIT_SHA");
+
+lazy_static! {
+    static ref CLIENT_LOG_URL: String = format!("{}/client_log", base_url());
+}
+
+thread_local! {
+    static BUFFER: RefCell<Vec<ClientLogEntry>> = const { RefCell::new(Vec::new()) };
+    static FLUSH_SCHEDULED: Cell<bool> = const { Cell::new(false) };
+    static USER: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum ClientLogLevel {
+    Warn,
+    Error,
+    Panic,
+}
+
+#[derive(Serialize, Debug)]
+struct ClientLogEntry {
+    level: ClientLogLevel,
+    message: String,
+    target: Option<String>,
+    route: Option<String>,
+    user: Option<String>,
+    version: &'static str,
+}
+
+impl ClientLogEntry {
+    fn new(level: ClientLogLevel, message: String, target: Option<String>) -> Self {
+        ClientLogEntry {
+            level,
+            message,
+            target,
+            route: web_sys::window().and_then(|w| w.location().pathname().ok()),
+            user: USER.with_borrow(Clone::clone),
+            version: VERSION,
+        }
+    }
+}
+
+/// Writes to the browser console and forwards warnings and errors to the backend
+struct ClientLogger {
+    level: Level,
+}
+
+impl Log for ClientLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let text = format!(
+            "{} {}:{} {}",
+            record.level(),
+            record.file().unwrap_or_else(|| record.target()),
+            record.line().unwrap_or_default(),
+            record.args()
+        );
+        let text = JsValue::from_str(&text);
+        match record.level() {
+            Level::Error => web_sys::console::error_1(&text),
+            Level::Warn => web_sys::console::warn_1(&text),
+            Level::Info => web_sys::console::info_1(&text),
+            Level::Debug => web_sys::console::log_1(&text),
+            Level::Trace => web_sys::console::debug_1(&text),
+        }
+        let level = match record.level() {
+            Level::Error => ClientLogLevel::Error,
+            Level::Warn => ClientLogLevel::Warn,
+            _ => return,
+        };
+        push(ClientLogEntry::new(
+            level,
+            record.args().to_string(),
+            Some(record.target().to_string()),
+        ));
+    }
+
+    fn flush(&self) {
+        flush();
+    }
+}
+
+/// Installs the logger, replaces the plain console logger
+pub fn init(level: Level) {
+    if log::set_boxed_logger(Box::new(ClientLogger { level })).is_ok() {
+        log::set_max_level(level.to_level_filter());
+    }
+}
+
+/// Panic hook: writes the panic to the console and reports it to the backend right away
+pub fn panic_hook(info: &PanicHookInfo) {
+    console_error_panic_hook::hook(info);
+    let mut entry = Some(ClientLogEntry::new(
+        ClientLogLevel::Panic,
+        info.to_string(),
+        None,
+    ));
+    let _ = BUFFER.try_with(|buffer| {
+        if let Ok(mut buffer) = buffer.try_borrow_mut() {
+            buffer.extend(entry.take());
+        }
+    });
+    match entry {
+        // the module is not usable after the panic, so no timer will fire anymore
+        None => flush(),
+        // the panic happened while the buffer was in use, e.g. in `push`
+        Some(entry) => send(&[entry]),
+    }
+}
+
+/// Sets the user reported together with log entries
+pub fn set_user(user: Option<String>) {
+    USER.set(user);
+}
+
+#[derive(Deserialize)]
+struct TokenClaims {
+    name: Option<String>,
+    preferred_username: Option<String>,
+}
+
+/// Extracts the user name from the payload of an access token
+pub fn token_user(access_token: &str) -> Option<String> {
+    let payload = access_token.split('.').nth(1)?;
+    let mut encoded: String = payload
+        .chars()
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+    while !encoded.len().is_multiple_of(4) {
+        encoded.push('=');
+    }
+    let json = web_sys::window()?.atob(&encoded).ok()?;
+    let claims: TokenClaims = serde_json::from_str(&json).ok()?;
+    claims.name.or(claims.preferred_username)
+}
+
+fn push(entry: ClientLogEntry) {
+    let size = BUFFER.with_borrow_mut(|buffer| {
+        buffer.push(entry);
+        buffer.len()
+    });
+    if size >= MAX_BATCH_SIZE {
+        flush();
+    } else if !FLUSH_SCHEDULED.replace(true) {
+        schedule_flush();
+    }
+}
+
+fn schedule_flush() {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let callback = Closure::once_into_js(|| {
+        FLUSH_SCHEDULED.set(false);
+        flush();
+    });
+    if window
+        .set_timeout_with_callback_and_timeout_and_arguments_0(
+            callback.unchecked_ref(),
+            FLUSH_DELAY_MS,
+        )
+        .is_err()
+    {
+        FLUSH_SCHEDULED.set(false);
+    }
+}
+
+/// Sends all buffered entries. Uses a beacon so it also works while unloading or after a panic.
+fn flush() {
+    let entries = BUFFER.with_borrow_mut(std::mem::take);
+    if !entries.is_empty() {
+        send(&entries);
+    }
+}
+
+fn send(entries: &[ClientLogEntry]) {
+    let Ok(body) = serde_json::to_string(entries) else {
+        return;
+    };
+    if let Some(window) = web_sys::window() {
+        // failures are not logged, this would only produce new entries to send
+        let _ = window
+            .navigator()
+            .send_beacon_with_opt_str(CLIENT_LOG_URL.as_str(), Some(&body));
+    }
+}
@@ -0,0 +1,74 @@
+use patternfly_yew::prelude::Brand;
+use yew::{Html, classes, function_component, html, use_context};
+
+use crate::graphql::settings::{BannerColor, SettingsClientConfig};
+
+/// Branding served by the backend, provided as context by `App`
+pub type ClientConfig = SettingsClientConfig;
+
+/// Logo of the masthead, the title if there is none
+#[function_component(AppBrand)]
+pub fn app_brand() -> Html {
+    let Some(config) = use_context::<ClientConfig>() else {
+        return Html::default();
+    };
+    match &config.logo_url {
+        Some(logo_url) => html! {
+            <Brand src={logo_url.clone()} alt={config.title.clone()}/>
+        },
+        None => html! {
+            <span class="pf-v6-c-title pf-m-xl">{config.title.clone()}</span>
+        },
+    }
+}
+
+/// Support links for the tools area of the masthead
+#[function_component(SupportLinks)]
+pub fn support_links() -> Html {
+    let Some(config) = use_context::<ClientConfig>() else {
+        return Html::default();
+    };
+    config
+        .support_links
+        .iter()
+        .map(|link| {
+            html! {
+                <a class="pf-v6-c-button pf-m-link" href={link.url.clone()}
+                    target="_blank" rel="noopener noreferrer">
+                    {link.label.clone()}
+                </a>
+            }
+        })
+        .collect()
+}
+
+/// Banner naming the environment like `STAGING`, empty if none is configured
+#[function_component(EnvironmentBanner)]
+pub fn environment_banner() -> Html {
+    let Some(environment) = use_context::<ClientConfig>().and_then(|config| config.environment)
+    else {
+        return Html::default();
+    };
+    let color = match environment.color {
+        BannerColor::RED => Some("pf-m-red"),
+        BannerColor::ORANGE => Some("pf-m-orange"),
+        BannerColor::YELLOW => Some("pf-m-yellow"),
+        BannerColor::GREEN => Some("pf-m-green"),
+        BannerColor::TEAL => Some("pf-m-teal"),
+        BannerColor::BLUE => Some("pf-m-blue"),
+        BannerColor::PURPLE => Some("pf-m-purple"),
+        BannerColor::DEFAULT | BannerColor::Other(_) => None,
+    };
+    html! {
+        <div class={classes!("pf-v6-c-banner", "pf-m-sticky", color)}>
+            {environment.text}
+        </div>
+    }
+}
+
+/// Shows the configured title in the browser tab
+pub fn set_document_title(config: &ClientConfig) {
+    if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+        document.set_title(&config.title);
+    }
+}
@@ -1 +1,3 @@
-
+pub mod branding;
+pub mod offline;
+pub mod session;
@@ -0,0 +1,55 @@
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use yew::{Callback, Html, Properties, function_component, html, use_effect_with};
+
+#[derive(Properties, PartialEq)]
+pub struct OfflineProps {
+    /// Clicked retry or the browser went online again
+    pub onretry: Callback<()>,
+}
+
+/// Shown instead of the app while the backend cannot be reached
+#[function_component(Offline)]
+pub fn offline(props: &OfflineProps) -> Html {
+    {
+        let onretry = props.onretry.clone();
+        use_effect_with((), move |_| {
+            let listener = Closure::<dyn Fn()>::new(move || onretry.emit(()));
+            let window = web_sys::window();
+            if let Some(window) = &window {
+                let _ = window
+                    .add_event_listener_with_callback("online", listener.as_ref().unchecked_ref());
+            }
+            move || {
+                if let Some(window) = &window {
+                    let _ = window.remove_event_listener_with_callback(
+                        "online",
+                        listener.as_ref().unchecked_ref(),
+                    );
+                }
+            }
+        });
+    }
+    html! {
+        <div class="pf-v6-c-empty-state">
+            <div class="pf-v6-c-empty-state__content">
+                <div class="pf-v6-c-empty-state__header">
+                    <div class="pf-v6-c-empty-state__title">
+                        <h1 class="pf-v6-c-empty-state__title-text">{"Server not reachable"}</h1>
+                    </div>
+                </div>
+                <div class="pf-v6-c-empty-state__body">
+                    {"Check your network connection, the app starts as soon as the server responds."}
+                </div>
+                <div class="pf-v6-c-empty-state__footer">
+                    <div class="pf-v6-c-empty-state__actions">
+                        <button class="pf-v6-c-button pf-m-primary" type="button"
+                            onclick={props.onretry.reform(|_| ())}>
+                            {"Retry"}
+                        </button>
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,61 @@
+use log::warn;
+use yew::{
+    Callback, Html, MouseEvent, Properties, function_component, html, use_context, use_effect_with,
+    use_state,
+};
+use yew_oauth2::context::OAuth2Context;
+use yew_oauth2::oauth2::use_auth_agent;
+use yew_oauth2::prelude::OAuth2Operations;
+
+use crate::graphql::GraphqlClient;
+
+#[derive(Properties, PartialEq)]
+pub struct SessionGuardProps {
+    pub children: Html,
+}
+
+/// Hands the login to the [GraphqlClient] and asks to log in again once the client could not
+/// get a refreshed access token, has to be placed inside `OAuth2`
+#[function_component(SessionGuard)]
+pub fn session_guard(props: &SessionGuardProps) -> Html {
+    let expired = use_state(|| false);
+    let client = use_context::<GraphqlClient>();
+    let authentication = use_context::<OAuth2Context>();
+    {
+        let expired = expired.setter();
+        use_effect_with(client.clone(), move |client| {
+            if let Some(client) = client {
+                client.on_session_expired(Callback::from(move |_| expired.set(true)));
+            }
+        });
+    }
+    use_effect_with((client, authentication), |(client, authentication)| {
+        if let (Some(client), Some(authentication)) = (client, authentication) {
+            client.set_authentication(authentication.clone());
+        }
+    });
+    let agent = use_auth_agent();
+    let login = Callback::from(move |_: MouseEvent| {
+        if let Some(agent) = &agent
+            && let Err(err) = agent.start_login()
+        {
+            warn!("Failed to start login: {err}");
+        }
+    });
+    html! {
+        <>
+            if *expired {
+                <div class="pf-v6-c-alert pf-m-warning pf-m-inline">
+                    <p class="pf-v6-c-alert__title">{"Your session has expired"}</p>
+                    <div class="pf-v6-c-alert__action-group">
+                        <button class="pf-v6-c-button pf-m-link pf-m-inline" type="button"
+                            onclick={login}>
+                            {"Log in again"}
+                        </button>
+                    </div>
+                </div>
+            }
+            {props.children.clone()}
+        </>
+    }
+}
@@ -52,4 +52,8 @@ pub enum FrontendError {
     Reqwest(#[from] reqwest::Error),
     #[error("Invalid http header")]
     InvalidHeader(#[from] InvalidHeaderValue),
+    #[error("Not authenticated")]
+    Unauthenticated,
+    #[error("Request timed out")]
+    Timeout,
 }
@@ -1,13 +1,9 @@
 use graphql_client::GraphQLQuery;
-use graphql_client::reqwest::post_graphql;
-use lazy_static::lazy_static;
-use reqwest::header::{AUTHORIZATION, HeaderMap};
-use yew::html::Scope;
-use yew::{Callback, Component};
-use yew_oauth2::context::OAuth2Context::Authenticated;
-use yew_oauth2::context::{Authentication, OAuth2Context};
 
-use crate::error::FrontendError;
+pub use client::GraphqlClient;
+
+mod client;
+pub mod middleware;
 
 #[derive(GraphQLQuery)]
 #[graphql(
@@ -21,58 +17,6 @@ pub struct Add;
 #[graphql(
     schema_path = "./graphql/anonymous_schema.graphql",
     query_path = "./graphql/settings.graphql",
-    response_derives = "Debug"
+    response_derives = "Debug, Clone, PartialEq"
 )]
 pub struct Settings;
-
-// ---------------------------------------------------
-// helper methods
-
-lazy_static! {
-    static ref GRAPHQL_URL: String = format!("{}/graphql", host());
-    static ref GRAPHQL_ANONYMOUS_URL: String = format!("{}/graphql_anonymous", host());
-}
-
-pub fn host() -> String {
-    let location = web_sys::window().unwrap().location();
-    let host = location.host().unwrap();
-    let protocol = location.protocol().unwrap();
-    format!("{protocol}//{host}")
-}
-
-/// Send Graphql-Query to server
-pub async fn query<Q: GraphQLQuery, S: Component>(
-    scope: Scope<S>,
-    request: Q::Variables,
-) -> Result<Q::ResponseData, FrontendError> {
-    let credentials = scope
-        .context::<OAuth2Context>(Callback::noop())
-        .map(|r| r.0);
-
-    let mut headers = HeaderMap::new();
-    if let Some(Authenticated(Authentication { access_token, .. })) = credentials.as_ref() {
-        headers.insert(AUTHORIZATION, format!("Bearer {access_token}").parse()?);
-    }
-    let client = reqwest::Client::builder()
-        .default_headers(headers)
-        .build()?;
-    let response = post_graphql::<Q, _>(&client, GRAPHQL_URL.as_str(), request).await?;
-    if let Some(data) = response.data {
-        Ok(data)
-    } else {
-        Err(FrontendError::Graphql(response.errors.unwrap_or_default()))
-    }
-}
-/// Send Graphql-Query to server
-pub async fn query_anonymous<Q: GraphQLQuery, S: Component>(
-    _scope: Scope<S>,
-    request: Q::Variables,
-) -> Result<Q::ResponseData, FrontendError> {
-    let client = reqwest::Client::builder().build()?;
-    let response = post_graphql::<Q, _>(&client, GRAPHQL_ANONYMOUS_URL.as_str(), request).await?;
-    if let Some(data) = response.data {
-        Ok(data)
-    } else {
-        Err(FrontendError::Graphql(response.errors.unwrap_or_default()))
-    }
-}
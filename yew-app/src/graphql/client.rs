@@ -0,0 +1,288 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::channel::mpsc;
+use futures::future::{Either, select};
+use graphql_client::{GraphQLQuery, QueryBody, Response};
+use log::warn;
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use yew::Callback;
+use yew::platform::time::sleep;
+use yew_oauth2::context::{Authentication, OAuth2Context};
+
+use super::middleware::{
+    BearerAuth, ClientInfo, Middleware, OutgoingRequest, RequestId, Timeout, TraceParent,
+};
+use crate::error::FrontendError;
+use crate::location::base_url;
+
+/// Time the oauth2 agent gets to refresh a rejected access token
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Graphql client shared by all components through a `ContextProvider<GraphqlClient>`
+///
+/// Reuses one connection pool, every request passes the [Middleware] chain. Requests rejected
+/// as unauthenticated are sent again once the oauth2 agent refreshed the access token, if it
+/// does not the session expired callback is called.
+#[derive(Clone)]
+pub struct GraphqlClient {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    http: reqwest::Client,
+    url: String,
+    anonymous_url: String,
+    middleware: Vec<Box<dyn Middleware>>,
+    session: RefCell<Session>,
+}
+
+#[derive(Default)]
+struct Session {
+    authentication: Option<OAuth2Context>,
+    /// requests waiting for a refreshed access token
+    waiting: Vec<mpsc::UnboundedSender<OAuth2Context>>,
+    on_expired: Option<Callback<()>>,
+    /// the server answered a request since the app started
+    connected: bool,
+    on_unreachable: Option<Callback<()>>,
+}
+
+impl Debug for GraphqlClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GraphqlClient")
+            .field("url", &self.inner.url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for GraphqlClient {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Default for GraphqlClient {
+    /// bearer token, request id, traceparent, client name and version, 30s timeout
+    fn default() -> Self {
+        Self::new(vec![
+            Box::new(Timeout(REQUEST_TIMEOUT)),
+            Box::new(ClientInfo {
+                name: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+            }),
+            Box::new(RequestId),
+            Box::new(TraceParent),
+            Box::new(BearerAuth),
+        ])
+    }
+}
+
+impl GraphqlClient {
+    pub fn new(middleware: Vec<Box<dyn Middleware>>) -> Self {
+        let base_url = base_url();
+        Self {
+            inner: Rc::new(Inner {
+                http: reqwest::Client::new(),
+                url: format!("{base_url}/graphql"),
+                anonymous_url: format!("{base_url}/graphql_anonymous"),
+                middleware,
+                session: RefCell::default(),
+            }),
+        }
+    }
+
+    /// Keeps the client in sync with the login, see `SessionGuard`
+    pub fn set_authentication(&self, authentication: OAuth2Context) {
+        let failed = matches!(authentication, OAuth2Context::Failed(_));
+        {
+            let mut session = self.inner.session.borrow_mut();
+            session
+                .waiting
+                .retain(|waiting| waiting.unbounded_send(authentication.clone()).is_ok());
+            session.authentication = Some(authentication);
+        }
+        // most likely the identity provider could not be reached
+        if failed {
+            self.unreachable();
+        }
+    }
+
+    /// Called when a rejected access token could not be refreshed
+    pub fn on_session_expired(&self, callback: Callback<()>) {
+        self.inner.session.borrow_mut().on_expired = Some(callback);
+    }
+
+    /// Called when the login fails or the server does not respond before any request succeeded
+    ///
+    /// An app shell cached by the service worker starts without a request to the server, this
+    /// is the first hint it is offline.
+    pub fn on_unreachable(&self, callback: Callback<()>) {
+        self.inner.session.borrow_mut().on_unreachable = Some(callback);
+    }
+
+    /// Send Graphql-Query to `/graphql` as the logged in user
+    pub async fn query<Q: GraphQLQuery>(
+        &self,
+        request: Q::Variables,
+    ) -> Result<Q::ResponseData, FrontendError> {
+        let body = Q::build_query(request);
+        let url = &self.inner.url;
+        let token = self.access_token();
+        match self.post(url, &body, token.as_deref()).await {
+            Err(FrontendError::Unauthenticated) => {}
+            Err(err @ (FrontendError::Reqwest(_) | FrontendError::Timeout)) => {
+                self.unreachable();
+                return Err(err);
+            }
+            result => return result,
+        }
+        let Some(token) = self.refreshed_token(token).await else {
+            self.session_expired();
+            return Err(FrontendError::Unauthenticated);
+        };
+        let result = self.post(url, &body, Some(&token)).await;
+        if let Err(FrontendError::Unauthenticated) = result {
+            self.session_expired();
+        }
+        result
+    }
+
+    /// Send Graphql-Query to `/graphql_anonymous`
+    pub async fn query_anonymous<Q: GraphQLQuery>(
+        &self,
+        request: Q::Variables,
+    ) -> Result<Q::ResponseData, FrontendError> {
+        self.post(&self.inner.anonymous_url, &Q::build_query(request), None)
+            .await
+    }
+
+    async fn post<V: Serialize, D: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &QueryBody<V>,
+        access_token: Option<&str>,
+    ) -> Result<D, FrontendError> {
+        let mut outgoing = OutgoingRequest {
+            access_token,
+            headers: HeaderMap::new(),
+            timeout: None,
+        };
+        for middleware in &self.inner.middleware {
+            middleware.handle(&mut outgoing);
+        }
+        let send = self
+            .inner
+            .http
+            .post(url)
+            .headers(outgoing.headers)
+            .json(body)
+            .send();
+        let response = match outgoing.timeout {
+            Some(timeout) => match select(Box::pin(send), Box::pin(sleep(timeout))).await {
+                Either::Left((response, _)) => response?,
+                Either::Right(_) => return Err(FrontendError::Timeout),
+            },
+            None => send.await?,
+        };
+        self.inner.session.borrow_mut().connected = true;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err(FrontendError::Unauthenticated);
+        }
+        let response: Response<D> = response.json().await?;
+        let errors = response.errors.unwrap_or_default();
+        if errors.iter().any(is_unauthenticated) {
+            return Err(FrontendError::Unauthenticated);
+        }
+        match response.data {
+            Some(data) => Ok(data),
+            None => Err(FrontendError::Graphql(errors)),
+        }
+    }
+
+    fn access_token(&self) -> Option<String> {
+        match &self.inner.session.borrow().authentication {
+            Some(OAuth2Context::Authenticated(Authentication { access_token, .. })) => {
+                Some(access_token.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Waits for the oauth2 agent to replace `rejected`, `None` if it fails or takes too long
+    ///
+    /// The agent refreshes the token on its own shortly before it expires, there is no way to
+    /// ask it earlier without leaving the page.
+    async fn refreshed_token(&self, rejected: Option<String>) -> Option<String> {
+        // refreshed while the request was in flight
+        if let Some(token) = self
+            .access_token()
+            .filter(|token| Some(token) != rejected.as_ref())
+        {
+            return Some(token);
+        }
+        let (sender, mut receiver) = mpsc::unbounded();
+        self.inner.session.borrow_mut().waiting.push(sender);
+        let refreshed = async {
+            while let Some(authentication) = receiver.next().await {
+                match authentication {
+                    OAuth2Context::Authenticated(Authentication { access_token, .. })
+                        if Some(&access_token) != rejected.as_ref() =>
+                    {
+                        return Some(access_token);
+                    }
+                    OAuth2Context::NotAuthenticated { .. } | OAuth2Context::Failed(_) => {
+                        return None;
+                    }
+                    _ => {}
+                }
+            }
+            None
+        };
+        let token = match select(Box::pin(refreshed), Box::pin(sleep(REFRESH_TIMEOUT))).await {
+            Either::Left((token, _)) => token,
+            Either::Right(_) => {
+                warn!("Access token was not refreshed within {REFRESH_TIMEOUT:?}");
+                None
+            }
+        };
+        receiver.close();
+        let mut session = self.inner.session.borrow_mut();
+        session.waiting.retain(|waiting| !waiting.is_closed());
+        token
+    }
+
+    fn unreachable(&self) {
+        let on_unreachable = {
+            let session = self.inner.session.borrow();
+            (!session.connected)
+                .then(|| session.on_unreachable.clone())
+                .flatten()
+        };
+        if let Some(on_unreachable) = on_unreachable {
+            on_unreachable.emit(());
+        }
+    }
+
+    fn session_expired(&self) {
+        let on_expired = self.inner.session.borrow().on_expired.clone();
+        if let Some(on_expired) = on_expired {
+            on_expired.emit(());
+        }
+    }
+}
+
+fn is_unauthenticated(error: &graphql_client::Error) -> bool {
+    error
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+        .is_some_and(|code| code == "UNAUTHENTICATED")
+}
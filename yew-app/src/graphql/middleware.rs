@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+
+const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+const TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
+const CLIENT_NAME: HeaderName = HeaderName::from_static("x-client-name");
+const CLIENT_VERSION: HeaderName = HeaderName::from_static("x-client-version");
+
+/// A graphql request before it is sent, adjusted by each [Middleware] in turn
+pub struct OutgoingRequest<'a> {
+    /// token of the logged in user, `None` for anonymous queries
+    pub access_token: Option<&'a str>,
+    pub headers: HeaderMap,
+    /// the request fails with `FrontendError::Timeout` after this long
+    pub timeout: Option<Duration>,
+}
+
+/// Step of the [GraphqlClient](super::GraphqlClient) applied to every request
+pub trait Middleware {
+    fn handle(&self, request: &mut OutgoingRequest);
+}
+
+impl<F: Fn(&mut OutgoingRequest)> Middleware for F {
+    fn handle(&self, request: &mut OutgoingRequest) {
+        self(request)
+    }
+}
+
+/// `Authorization: Bearer ...` with the access token
+pub struct BearerAuth;
+
+impl Middleware for BearerAuth {
+    fn handle(&self, request: &mut OutgoingRequest) {
+        if let Some(value) = request
+            .access_token
+            .and_then(|token| HeaderValue::from_str(&format!("Bearer {token}")).ok())
+        {
+            request.headers.insert(AUTHORIZATION, value);
+        }
+    }
+}
+
+/// Random `X-Request-Id` to find a request in the logs of the backend
+pub struct RequestId;
+
+impl Middleware for RequestId {
+    fn handle(&self, request: &mut OutgoingRequest) {
+        insert(&mut request.headers, REQUEST_ID, random_hex(16));
+    }
+}
+
+/// W3C `traceparent` starting a sampled trace per request
+pub struct TraceParent;
+
+impl Middleware for TraceParent {
+    fn handle(&self, request: &mut OutgoingRequest) {
+        let traceparent = format!("00-{}-{}-01", random_hex(16), random_hex(8));
+        insert(&mut request.headers, TRACEPARENT, traceparent);
+    }
+}
+
+/// `X-Client-Name` and `X-Client-Version` of the frontend build
+pub struct ClientInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+impl Middleware for ClientInfo {
+    fn handle(&self, request: &mut OutgoingRequest) {
+        insert(&mut request.headers, CLIENT_NAME, self.name.to_string());
+        insert(
+            &mut request.headers,
+            CLIENT_VERSION,
+            self.version.to_string(),
+        );
+    }
+}
+
+/// Gives up on requests taking longer than the duration
+pub struct Timeout(pub Duration);
+
+impl Middleware for Timeout {
+    fn handle(&self, request: &mut OutgoingRequest) {
+        request.timeout = Some(self.0);
+    }
+}
+
+fn insert(headers: &mut HeaderMap, name: HeaderName, value: String) {
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        headers.insert(name, value);
+    }
+}
+
+/// `bytes` random bytes from `crypto.getRandomValues` as lowercase hex, `Math.random` fills in
+/// where crypto is unavailable, the all zero ids of an empty buffer are invalid
+fn random_hex(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    let filled = web_sys::window()
+        .and_then(|window| window.crypto().ok())
+        .is_some_and(|crypto| crypto.get_random_values_with_u8_array(&mut buffer).is_ok());
+    if !filled {
+        for byte in &mut buffer {
+            *byte = (js_sys::Math::random() * 256.0) as u8;
+        }
+    }
+    buffer.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,19 @@
+/// Url the app is served under without trailing slash, like `https://corp/tools/fullstack`
+///
+/// Taken from the `<base href>` the backend renders into `index.html` for `server.base_path`.
+pub fn base_url() -> String {
+    let window = web_sys::window().unwrap();
+    let base = window
+        .document()
+        .and_then(|document| document.base_uri().ok().flatten())
+        .unwrap_or_else(|| window.location().origin().unwrap());
+    base.trim_end_matches('/').to_string()
+}
+
+/// Path of [base_url] like `/tools/fullstack`, empty if served at the root
+pub fn base_path() -> String {
+    let base = base_url();
+    web_sys::Url::new(&base)
+        .map(|url| url.pathname().trim_end_matches('/').to_string())
+        .unwrap_or_default()
+}
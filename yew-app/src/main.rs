@@ -1,23 +1,48 @@
 use wasm_bindgen::JsValue;
 use wasm_bindgen::prelude::wasm_bindgen;
+#[cfg(not(debug_assertions))]
+use wasm_bindgen_futures::{JsFuture, spawn_local};
 
 use pages::app::App;
 
+mod client_log;
 pub mod components;
 mod error;
 mod graphql;
+mod location;
 pub mod pages;
+mod runtime_config;
 
 #[wasm_bindgen]
 pub fn init_panic_hook() {
-    console_error_panic_hook::set_once();
+    std::panic::set_hook(Box::new(client_log::panic_hook));
 }
 #[cfg(not(debug_assertions))]
 const LOG_LEVEL: log::Level = log::Level::Info;
 #[cfg(debug_assertions)]
 const LOG_LEVEL: log::Level = log::Level::Trace;
 pub fn main() -> Result<(), JsValue> {
-    wasm_logger::init(wasm_logger::Config::new(LOG_LEVEL));
+    client_log::init(LOG_LEVEL);
+    init_panic_hook();
+    #[cfg(not(debug_assertions))]
+    register_service_worker();
     yew::Renderer::<App>::new().render();
     Ok(())
 }
+
+/// Caches the app for offline use, `sw.js` is generated by the backend
+///
+/// Left out of debug builds, `trunk serve` has no worker and a cached shell hides changes.
+#[cfg(not(debug_assertions))]
+fn register_service_worker() {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    // relative to the <base href>, the worker controls the whole base path
+    let registration = window.navigator().service_worker().register("sw.js");
+    spawn_local(async move {
+        if let Err(error) = JsFuture::from(registration).await {
+            log::warn!("Cannot register service worker: {error:?}");
+        }
+    });
+}
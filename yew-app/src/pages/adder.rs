@@ -3,12 +3,14 @@ use patternfly_yew::prelude::{Form, TextInput, TextInputType};
 use wasm_bindgen_futures::spawn_local;
 use yew::{
     html::Scope,
-    prelude::{Component, Context, Html, html},
+    prelude::{Callback, Component, Context, Html, html},
 };
 
-use crate::graphql::{Add, query};
+use crate::graphql::{Add, GraphqlClient};
 
 pub struct Adder {
+    /// provided by App around the router, the client never changes
+    client: Option<GraphqlClient>,
     a: i64,
     b: i64,
     c: i64,
@@ -18,9 +20,13 @@ impl Adder {
     fn calculate(&self, scope: Scope<Adder>) {
         let a = self.a;
         let b = self.b;
+        let Some(client) = self.client.clone() else {
+            return;
+        };
         spawn_local(async move {
-            let result =
-                query::<Add, _>(scope.clone(), crate::graphql::add::Variables { a, b }).await;
+            let result = client
+                .query::<Add>(crate::graphql::add::Variables { a, b })
+                .await;
             match result {
                 Ok(value) => scope.send_message(AddMessage::ResultUpdate { a, b, c: value.add }),
                 Err(err) => error!("Error on server {err:?}"),
@@ -39,8 +45,20 @@ impl Component for Adder {
     type Message = AddMessage;
     type Properties = ();
 
-    fn create(_ctx: &Context<Self>) -> Self {
-        Adder { a: 0, b: 0, c: 0 }
+    fn create(ctx: &Context<Self>) -> Self {
+        let client = ctx
+            .link()
+            .context::<GraphqlClient>(Callback::noop())
+            .map(|(client, _)| client);
+        if client.is_none() {
+            error!("Adder rendered outside of the GraphqlClient context");
+        }
+        Adder {
+            client,
+            a: 0,
+            b: 0,
+            c: 0,
+        }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
@@ -71,6 +89,9 @@ impl Component for Adder {
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        if self.client.is_none() {
+            return html! {};
+        }
         let a_edit = ctx.link().batch_callback(|value: String| {
             let n: i64 = value.parse().ok()?;
             Some(AddMessage::AChanged(n))
@@ -1,13 +1,16 @@
-use log::error;
+use log::{error, warn};
 use patternfly_yew::prelude::{
     BackdropViewer, Nav, NavItem, NavRouterItem, Page, PageSidebar, ToastViewer,
 };
 use wasm_bindgen_futures::spawn_local;
 use yew::Context;
-use yew::{Callback, MouseEvent, function_component};
+use yew::{
+    Callback, ContextProvider, MouseEvent, function_component, use_context, use_effect_with,
+};
 use yew::{Html, Properties, html, html_nested};
 use yew_nested_router::Router;
 use yew_nested_router::prelude::{Switch as RouterSwitch, Target};
+use yew_oauth2::context::{Authentication, OAuth2Context};
 use yew_oauth2::oauth2::LocationRedirect;
 use yew_oauth2::oauth2::{OAuth2, use_auth_agent};
 use yew_oauth2::prelude::Failure;
@@ -15,9 +18,18 @@ use yew_oauth2::prelude::NotAuthenticated;
 use yew_oauth2::prelude::oauth2::Config;
 use yew_oauth2::prelude::{Authenticated, OAuth2Operations};
 
+use crate::client_log;
+use crate::components::branding::{
+    AppBrand, ClientConfig, EnvironmentBanner, SupportLinks, set_document_title,
+};
+use crate::components::offline::Offline;
+use crate::components::session::SessionGuard;
+use crate::error::FrontendError;
 use crate::graphql::settings::{ResponseData, SettingsAuthentication};
-use crate::graphql::{Settings, query_anonymous, settings};
+use crate::graphql::{GraphqlClient, Settings, settings};
+use crate::location::base_path;
 use crate::pages::adder::Adder;
+use crate::runtime_config;
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Target)]
 pub enum AppRoute {
@@ -33,6 +45,10 @@ pub enum AppRoute {
 #[derive(Debug)]
 pub struct App {
     oauth2_config: Option<Config>,
+    client_config: Option<ClientConfig>,
+    client: GraphqlClient,
+    /// the backend could not be reached
+    offline: bool,
 }
 
 #[derive(Properties, PartialEq)]
@@ -54,36 +70,95 @@ fn switch_main(switch: AppRoute) -> Html {
 fn switch_unauthenticated(switch: AppRoute) -> Html {
     match switch {
         AppRoute::Home => html! {  <p> { "You need to log in" } </p>},
-        _ => html!(<LocationRedirect logout_href="/" />),
+        _ => html!(<LocationRedirect logout_href={format!("{}/", base_path())} />),
     }
 }
 
 #[derive(Debug)]
 pub enum AppMessage {
-    AuthenticationData(Config),
+    Settings {
+        oauth2_config: Config,
+        client_config: ClientConfig,
+    },
+    Offline,
+    /// fetches the settings, which shows whether the backend is reachable
+    Retry,
+}
+
+impl From<ResponseData> for AppMessage {
+    fn from(settings: ResponseData) -> Self {
+        let ResponseData {
+            authentication:
+                SettingsAuthentication {
+                    auth_url,
+                    client_id,
+                    token_url,
+                },
+            client_config,
+        } = settings;
+        AppMessage::Settings {
+            oauth2_config: Config::new(client_id, auth_url, token_url),
+            client_config,
+        }
+    }
 }
 
 impl yew::Component for App {
     type Message = AppMessage;
     type Properties = ();
-    fn create(_ctx: &Context<Self>) -> Self {
-        Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut app = Self {
             oauth2_config: None,
+            client_config: None,
+            client: GraphqlClient::default(),
+            offline: false,
+        };
+        // settings from a cached index.html do not tell if the backend is reachable
+        app.client
+            .on_unreachable(ctx.link().callback(|_| AppMessage::Retry));
+        if let Some(settings) = runtime_config::settings() {
+            app.update(ctx, settings.into());
         }
+        app
     }
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            AppMessage::AuthenticationData(config) => {
-                self.oauth2_config = Some(config);
+            AppMessage::Settings {
+                oauth2_config,
+                client_config,
+            } => {
+                set_document_title(&client_config);
+                self.oauth2_config = Some(oauth2_config);
+                self.client_config = Some(client_config);
+                self.offline = false;
                 true
             }
+            AppMessage::Offline => {
+                self.offline = true;
+                true
+            }
+            AppMessage::Retry => {
+                self.fetch_settings(ctx);
+                false
+            }
         }
     }
 
-    fn view(&self, _ctx: &Context<Self>) -> Html {
-        if let Some(config) = self.oauth2_config.clone() {
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        if self.offline {
+            html! {
+                <Offline onretry={ctx.link().callback(|_| AppMessage::Retry)}/>
+            }
+        } else if let (Some(config), Some(client_config)) =
+            (self.oauth2_config.clone(), self.client_config.clone())
+        {
             html! {
-                <MainOAuth2 {config}/>
+                <ContextProvider<ClientConfig> context={client_config}>
+                    <ContextProvider<GraphqlClient> context={self.client.clone()}>
+                        <EnvironmentBanner/>
+                        <MainOAuth2 {config}/>
+                    </ContextProvider<GraphqlClient>>
+                </ContextProvider<ClientConfig>>
             }
         } else {
             html! {
@@ -92,43 +167,64 @@ impl yew::Component for App {
         }
     }
     fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
-        if first_render {
-            let scope = ctx.link().clone();
-            spawn_local(async move {
-                let result =
-                    query_anonymous::<Settings, _>(scope.clone(), settings::Variables {}).await;
-                match result {
-                    Ok(ResponseData {
-                        authentication:
-                            SettingsAuthentication {
-                                auth_url,
-                                client_id,
-                                token_url,
-                            },
-                    }) => {
-                        scope.send_message(AppMessage::AuthenticationData(Config::new(
-                            client_id, auth_url, token_url,
-                        )));
-                    }
-                    Err(err) => error!("Error on server {err:?}"),
-                }
-            });
+        // without settings embedded into index.html
+        if first_render && self.oauth2_config.is_none() {
+            self.fetch_settings(ctx);
         }
     }
 }
 
+impl App {
+    fn fetch_settings(&self, ctx: &Context<Self>) {
+        let scope = ctx.link().clone();
+        let client = self.client.clone();
+        spawn_local(async move {
+            let result = client
+                .query_anonymous::<Settings>(settings::Variables {})
+                .await;
+            match result {
+                Ok(settings) => scope.send_message(AppMessage::from(settings)),
+                Err(err @ (FrontendError::Reqwest(_) | FrontendError::Timeout)) => {
+                    warn!("Server not reachable: {err}");
+                    scope.send_message(AppMessage::Offline);
+                }
+                Err(err) => error!("Error on server {err:?}"),
+            }
+        });
+    }
+}
+
 #[function_component(MainOAuth2)]
 fn main_oauth2(props: &Props) -> Html {
     let oauth2_config = &props.config;
     html! {
         <OAuth2 config={oauth2_config.clone()}>
-            <Router<AppRoute> default={AppRoute::Home}>
-                <MainPage/>
-            </Router<AppRoute>>
+            <SessionGuard>
+                <ClientLogUser/>
+                <Router<AppRoute> default={AppRoute::Home} base={base_path()}>
+                    <MainPage/>
+                </Router<AppRoute>>
+            </SessionGuard>
         </OAuth2>
     }
 }
 
+/// Keeps the user reported in frontend log entries in sync with the login state
+#[function_component(ClientLogUser)]
+fn client_log_user() -> Html {
+    let context = use_context::<OAuth2Context>();
+    use_effect_with(context, |context| {
+        let user = match context {
+            Some(OAuth2Context::Authenticated(Authentication { access_token, .. })) => {
+                client_log::token_user(access_token)
+            }
+            _ => None,
+        };
+        client_log::set_user(user);
+    });
+    Html::default()
+}
+
 #[function_component(MainPage)]
 fn main_page() -> Html {
     html! {
@@ -136,16 +232,17 @@ fn main_page() -> Html {
             <ToastViewer>
                 <Failure>{"Fail"}</Failure>
                 <Authenticated>
-                    <Page sidebar={html_nested! {<PageSidebar><AuthenticatedSidebar/></PageSidebar>}} full_height={true}>
+                    <Page sidebar={html_nested! {<PageSidebar><AuthenticatedSidebar/></PageSidebar>}} full_height={true}
+                        brand={html! {<AppBrand/>}} tools={html! {<SupportLinks/>}}>
                       //<ToastViewer/>
-                      //logo={logo}
                         <RouterSwitch<AppRoute>
                             render = { switch_main}
                         />
                     </Page>
                 </Authenticated>
                 <NotAuthenticated>
-                    <Page sidebar={html_nested! {<PageSidebar><NotAuthenticatedSidebar/></PageSidebar>}} full_height={true}>
+                    <Page sidebar={html_nested! {<PageSidebar><NotAuthenticatedSidebar/></PageSidebar>}} full_height={true}
+                        brand={html! {<AppBrand/>}} tools={html! {<SupportLinks/>}}>
                         <RouterSwitch<AppRoute>
                             render = {switch_unauthenticated}
                         />
@@ -0,0 +1,19 @@
+use log::warn;
+
+use crate::graphql::settings::ResponseData;
+
+/// `id` of the script tag the backend renders into `index.html`
+const ELEMENT_ID: &str = "runtime-config";
+
+/// Response of the `Settings` query embedded by the backend
+///
+/// `None` if the page was served without, e.g. by `trunk serve`, the query is sent then.
+pub fn settings() -> Option<ResponseData> {
+    let element = web_sys::window()?
+        .document()?
+        .get_element_by_id(ELEMENT_ID)?;
+    let json = element.text_content()?;
+    serde_json::from_str(&json)
+        .map_err(|error| warn!("Ignoring invalid runtime config: {error}"))
+        .ok()
+}